@@ -1,13 +1,20 @@
 use bytes::Bytes;
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 use webrtc::rtp::header::Header;
 use webrtc::rtp::packet::Packet;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::TrackLocal;
 
 // Import from the sfu library
-use sfu::broadcaster::{BroadcasterWriter, TrackBroadcaster};
-use sfu::media_setup::MediaSetup;
+use sfu::broadcaster::{CoalesceConfig, NackBuffer, TrackBroadcaster};
+use sfu::congestion::CongestionController;
+use sfu::media_setup::{IceServerConfig, MediaSetup};
+use sfu::quality::ConnectionQualityTracker;
+use sfu::stats::OutboundRtpStats;
 
 // 1. Benchmark Packet Cloning (Hot Path Simulation)
 // Simulates the cost of cloning a packet for each subscriber
@@ -65,37 +72,11 @@ fn bench_string_cloning(c: &mut Criterion) {
 }
 
 // 3. Benchmark Broadcaster Write Loop (Async Hot Path)
-// Measures the actual broadcast loop performance with 100 subscribers
+// Measures the real `broadcast()` fan-out with 100 subscribed writers, each
+// running its own consumer task off the shared broadcast channel.
 fn bench_broadcast_loop(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let api = MediaSetup::create_webrtc_api();
-    let config = MediaSetup::get_rtc_config();
-
-    // Setup Broadcaster with a real PC (required for struct validation)
-    let pc = rt.block_on(api.new_peer_connection(config)).unwrap();
-
-    let broadcaster = Arc::new(TrackBroadcaster::new(
-        "video".to_string(),
-        Default::default(),
-        Arc::new(pc),
-        12345,
-    ));
-
-    // Inject 100 dummy writers (subscribers)
-    // We use a dummy channel that simply drops the messages
-    let mut writers = rt.block_on(broadcaster.writers.write());
-    for i in 0..100 {
-        let (tx, _rx) = tokio::sync::mpsc::channel(100);
-        // calculate ssrc
-        let ssrc = 1000 + i;
-        writers.push(BroadcasterWriter {
-            tx,
-            ssrc,
-            payload_type: 96,
-        });
-    }
-    // Release lock
-    drop(writers);
+    let broadcaster = rt.block_on(setup_broadcaster_with_writers(100));
 
     let packet = Packet {
         header: Header {
@@ -107,22 +88,240 @@ fn bench_broadcast_loop(c: &mut Criterion) {
 
     let mut group = c.benchmark_group("broadcaster");
     group.bench_function("broadcast_100_subscribers", |b| {
-        b.to_async(&rt).iter(|| {
-            let mut p = packet.clone();
-            let bc = broadcaster.clone();
-            async move {
-                bc.broadcast(&mut p).await;
-            }
+        b.iter(|| {
+            broadcaster.broadcast(&packet);
         })
     });
 
     group.finish();
 }
 
+// 4. Zero-copy fan-out vs. clone-per-subscriber (chunk4-5)
+// `TrackBroadcaster::broadcast` sends one `Arc<Packet>` onto a broadcast
+// channel regardless of subscriber count; the per-writer SSRC/PT rewrite
+// happens lazily inside each writer's own consumer task. This compares that
+// against the cost the old design paid up front: cloning the full packet
+// (header + payload) once per subscriber before fan-out.
+fn bench_zero_copy_vs_clone_per_subscriber(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let packet = Packet {
+        header: Header {
+            ssrc: 12345,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; 1200]),
+    };
+
+    let mut group = c.benchmark_group("zero_copy_vs_clone_per_subscriber");
+    for &subscribers in &[100usize, 500usize] {
+        let broadcaster = rt.block_on(setup_broadcaster_with_writers(subscribers));
+
+        group.bench_with_input(
+            BenchmarkId::new("arc_shared_broadcast", subscribers),
+            &subscribers,
+            |b, _| {
+                b.iter(|| {
+                    broadcaster.broadcast(&packet);
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("clone_per_subscriber", subscribers),
+            &subscribers,
+            |b, &subscribers| {
+                b.iter(|| {
+                    for _ in 0..subscribers {
+                        let _ = packet.clone();
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// 5. Coalesced vs per-packet write pump (chunk4-6)
+// `TrackBroadcaster::spawn_writer_consumer` coalesces packets up to
+// `CoalesceConfig::bytes_threshold` (or `flush_interval`) before flushing in
+// one batch and yielding, instead of writing and rescheduling once per
+// packet. This drives a burst of packets through a real writer under both a
+// `CoalesceConfig` that forces an effectively per-packet flush (threshold of
+// a single byte) and the production default, measuring wall-clock time for
+// every packet in the burst to reach `write_rtp`.
+fn bench_coalesced_vs_per_packet_write(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let packet_count: u64 = 200;
+    let packet = Packet {
+        header: Header {
+            ssrc: 12345,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; 1200]),
+    };
+
+    let mut group = c.benchmark_group("coalesced_vs_per_packet_write");
+    group.sample_size(20);
+
+    let configs = [
+        (
+            "per_packet",
+            CoalesceConfig {
+                bytes_threshold: 1,
+                flush_interval: Duration::from_millis(0),
+            },
+        ),
+        ("coalesced_default", CoalesceConfig::default()),
+    ];
+
+    for (label, config) in configs {
+        group.bench_function(label, |b| {
+            b.iter_batched(
+                || rt.block_on(setup_broadcaster_with_config(10, config)),
+                |(broadcaster, stats)| {
+                    rt.block_on(async {
+                        for _ in 0..packet_count {
+                            broadcaster.broadcast(&packet);
+                        }
+                        while stats.snapshot().packets_forwarded < packet_count {
+                            tokio::task::yield_now().await;
+                        }
+                    })
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// Like `setup_broadcaster_with_writers`, but with an explicit
+/// `CoalesceConfig` and returning the first writer's stats alongside the
+/// broadcaster, so a caller can poll `packets_forwarded` to know when a
+/// burst it sent has actually been written.
+async fn setup_broadcaster_with_config(
+    count: usize,
+    coalesce: CoalesceConfig,
+) -> (Arc<TrackBroadcaster>, Arc<OutboundRtpStats>) {
+    let api = MediaSetup::create_webrtc_api(None);
+    let config = MediaSetup::get_rtc_config(&IceServerConfig::from_env());
+
+    let source_pc = Arc::new(api.new_peer_connection(config.clone()).await.unwrap());
+    let downstream_pc = Arc::new(api.new_peer_connection(config).await.unwrap());
+
+    let broadcaster = Arc::new(TrackBroadcaster::with_coalesce_config(
+        "video".to_string(),
+        RTCRtpCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        source_pc,
+        12345,
+        coalesce,
+        true,
+    ));
+
+    let mut first_stats = None;
+    for i in 0..count {
+        let track = Arc::new(TrackLocalStaticRTP::new(
+            RTCRtpCodecCapability {
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            format!("track-{}", i),
+            "stream-bench".to_owned(),
+        ));
+        let sender = downstream_pc
+            .add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>)
+            .await
+            .unwrap();
+        let stats = Arc::new(OutboundRtpStats::new());
+        if i == 0 {
+            first_stats = Some(stats.clone());
+        }
+
+        broadcaster
+            .add_writer(
+                track,
+                1000 + i as u32,
+                96,
+                format!("subscriber-{}", i),
+                stats,
+                Arc::new(ConnectionQualityTracker::new()),
+                downstream_pc.clone(),
+                sender,
+                Arc::new(NackBuffer::new()),
+                Arc::new(CongestionController::from_env()),
+                None,
+            )
+            .await;
+    }
+
+    (broadcaster, first_stats.expect("count > 0"))
+}
+
+/// Builds a `TrackBroadcaster` with `count` real writers (each backed by its
+/// own `TrackLocalStaticRTP` added to a shared downstream `PeerConnection`),
+/// so `broadcast()` exercises the same fan-out path production code does.
+async fn setup_broadcaster_with_writers(count: usize) -> Arc<TrackBroadcaster> {
+    let api = MediaSetup::create_webrtc_api(None);
+    let config = MediaSetup::get_rtc_config(&IceServerConfig::from_env());
+
+    let source_pc = Arc::new(api.new_peer_connection(config.clone()).await.unwrap());
+    let downstream_pc = Arc::new(api.new_peer_connection(config).await.unwrap());
+
+    let broadcaster = Arc::new(TrackBroadcaster::new(
+        "video".to_string(),
+        RTCRtpCodecCapability {
+            mime_type: "video/VP8".to_owned(),
+            ..Default::default()
+        },
+        source_pc,
+        12345,
+    ));
+
+    for i in 0..count {
+        let track = Arc::new(TrackLocalStaticRTP::new(
+            RTCRtpCodecCapability {
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            format!("track-{}", i),
+            "stream-bench".to_owned(),
+        ));
+        let sender = downstream_pc
+            .add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>)
+            .await
+            .unwrap();
+
+        broadcaster
+            .add_writer(
+                track,
+                1000 + i as u32,
+                96,
+                format!("subscriber-{}", i),
+                Arc::new(OutboundRtpStats::new()),
+                Arc::new(ConnectionQualityTracker::new()),
+                downstream_pc.clone(),
+                sender,
+                Arc::new(NackBuffer::new()),
+                Arc::new(CongestionController::from_env()),
+                None,
+            )
+            .await;
+    }
+
+    broadcaster
+}
+
 criterion_group!(
     benches,
     bench_packet_cloning,
     bench_string_cloning,
-    bench_broadcast_loop
+    bench_broadcast_loop,
+    bench_zero_copy_vs_clone_per_subscriber,
+    bench_coalesced_vs_per_packet_write
 );
 criterion_main!(benches);
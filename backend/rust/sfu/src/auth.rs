@@ -0,0 +1,227 @@
+//! JWT-signed room access tokens.
+//!
+//! Clients present a token (minted by an authorization server outside this
+//! process, see [`mint_access_token`]) alongside `CreateSessionRequest`. The
+//! SFU verifies it against `SFU_JWT_SECRET` and keeps the decoded
+//! [`RoomGrant`] on the [`crate::peer_manager::Peer`] so later forwarding
+//! decisions (`on_track`, `subscribe_to_existing_tracks`) can check
+//! `can_publish`/`can_subscribe` without re-parsing or re-verifying it.
+//!
+//! If `SFU_JWT_SECRET` isn't set, verification is skipped and every peer is
+//! granted full access, so local development doesn't need a signing key.
+
+use serde::{Deserialize, Serialize};
+
+const ALGORITHM: jsonwebtoken::Algorithm = jsonwebtoken::Algorithm::HS256;
+
+fn default_true() -> bool {
+    true
+}
+
+/// The claims carried by a room access token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomGrant {
+    /// Room this token is valid for; must match the `room_id` the holder
+    /// presents it for.
+    pub room: String,
+    /// Subject — the user ID this token was issued to.
+    pub sub: String,
+    #[serde(default = "default_true")]
+    pub can_publish: bool,
+    #[serde(default = "default_true")]
+    pub can_subscribe: bool,
+    /// Whether inbound data channel messages (chat, admin actions, reactions,
+    /// ...) from this peer are processed, independent of `can_publish` which
+    /// only gates media tracks.
+    #[serde(default = "default_true")]
+    pub can_publish_data: bool,
+    /// Source user IDs this grant may publish tracks on behalf of, beyond
+    /// `sub` itself (e.g. a recording/bridge service republishing another
+    /// participant's media). Empty means "just `sub`".
+    #[serde(default)]
+    pub can_publish_sources: Vec<String>,
+    /// Standard JWT expiry (Unix seconds). Enforced by `jsonwebtoken`'s
+    /// validation during `AuthConfig::verify`, not re-checked here.
+    pub exp: u64,
+}
+
+impl RoomGrant {
+    /// Whether this grant permits publishing a track whose source/stream
+    /// owner is `source_user_id` (either the token holder themself, or one
+    /// of the delegated `can_publish_sources`).
+    pub fn may_publish_as(&self, source_user_id: &str) -> bool {
+        self.can_publish
+            && (source_user_id == self.sub
+                || self.can_publish_sources.iter().any(|s| s == source_user_id))
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    /// Token failed signature/expiry/shape validation.
+    Invalid(jsonwebtoken::errors::Error),
+    /// Token verified but was issued for a different room.
+    RoomMismatch { expected: String, got: String },
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Invalid(e) => write!(f, "invalid access token: {}", e),
+            AuthError::RoomMismatch { expected, got } => {
+                write!(f, "token is for room '{}', not '{}'", got, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Verifies room access tokens against a signing secret loaded from the
+/// environment. Cheap to clone (the secret is the only state); `MySfu` holds
+/// one and every `CreateSession` call reuses it.
+#[derive(Clone)]
+pub struct AuthConfig {
+    secret: Option<std::sync::Arc<Vec<u8>>>,
+}
+
+impl AuthConfig {
+    /// Reads `SFU_JWT_SECRET`. Unset means access tokens aren't required —
+    /// every call to `verify` is granted full access to the room it asks
+    /// for, which is the right default for local/dev deployments that don't
+    /// run a separate authorization service.
+    pub fn from_env() -> Self {
+        let secret = std::env::var("SFU_JWT_SECRET").ok().map(|s| std::sync::Arc::new(s.into_bytes()));
+        Self { secret }
+    }
+
+    /// Verifies `token` for `room_id`/`user_id`, returning the decoded
+    /// grant. With no `SFU_JWT_SECRET` configured, `token` is ignored and a
+    /// full-access grant for `room_id`/`user_id` is returned.
+    pub fn verify(&self, token: &str, room_id: &str, user_id: &str) -> Result<RoomGrant, AuthError> {
+        let Some(secret) = &self.secret else {
+            return Ok(RoomGrant {
+                room: room_id.to_string(),
+                sub: user_id.to_string(),
+                can_publish: true,
+                can_subscribe: true,
+                can_publish_data: true,
+                can_publish_sources: Vec::new(),
+                exp: u64::MAX,
+            });
+        };
+
+        let mut validation = jsonwebtoken::Validation::new(ALGORITHM);
+        validation.sub = Some(user_id.to_string());
+        let data = jsonwebtoken::decode::<RoomGrant>(
+            token,
+            &jsonwebtoken::DecodingKey::from_secret(secret),
+            &validation,
+        )
+        .map_err(AuthError::Invalid)?;
+
+        if data.claims.room != room_id {
+            return Err(AuthError::RoomMismatch {
+                expected: room_id.to_string(),
+                got: data.claims.room,
+            });
+        }
+
+        Ok(data.claims)
+    }
+}
+
+/// Mints a signed access token for `grant`. The SFU itself never calls
+/// this — it only verifies — but an authorization service (or a test) needs
+/// it to produce tokens `AuthConfig::verify` will accept.
+pub fn mint_access_token(grant: &RoomGrant, secret: &[u8]) -> Result<String, AuthError> {
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(ALGORITHM),
+        grant,
+        &jsonwebtoken::EncodingKey::from_secret(secret),
+    )
+    .map_err(AuthError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(room: &str, user: &str) -> RoomGrant {
+        RoomGrant {
+            room: room.to_string(),
+            sub: user.to_string(),
+            can_publish: true,
+            can_subscribe: true,
+            can_publish_data: true,
+            can_publish_sources: Vec::new(),
+            exp: 9_999_999_999,
+        }
+    }
+
+    #[test]
+    fn round_trips_a_minted_token() {
+        let secret = b"test-secret";
+        let token = mint_access_token(&grant("room1", "alice"), secret).unwrap();
+
+        let config = AuthConfig {
+            secret: Some(std::sync::Arc::new(secret.to_vec())),
+        };
+        let decoded = config.verify(&token, "room1", "alice").unwrap();
+        assert_eq!(decoded.sub, "alice");
+        assert!(decoded.can_publish);
+    }
+
+    #[test]
+    fn rejects_a_token_for_the_wrong_room() {
+        let secret = b"test-secret";
+        let token = mint_access_token(&grant("room1", "alice"), secret).unwrap();
+
+        let config = AuthConfig {
+            secret: Some(std::sync::Arc::new(secret.to_vec())),
+        };
+        let err = config.verify(&token, "room2", "alice").unwrap_err();
+        assert!(matches!(err, AuthError::RoomMismatch { .. }));
+    }
+
+    #[test]
+    fn rejects_an_expired_token() {
+        let secret = b"test-secret";
+        let mut expired_grant = grant("room1", "alice");
+        expired_grant.exp = 1;
+        let token = mint_access_token(&expired_grant, secret).unwrap();
+
+        let config = AuthConfig {
+            secret: Some(std::sync::Arc::new(secret.to_vec())),
+        };
+        let err = config.verify(&token, "room1", "alice").unwrap_err();
+        assert!(matches!(err, AuthError::Invalid(_)));
+    }
+
+    #[test]
+    fn rejects_a_token_signed_with_a_different_secret() {
+        let token = mint_access_token(&grant("room1", "alice"), b"secret-a").unwrap();
+
+        let config = AuthConfig {
+            secret: Some(std::sync::Arc::new(b"secret-b".to_vec())),
+        };
+        assert!(config.verify(&token, "room1", "alice").is_err());
+    }
+
+    #[test]
+    fn grants_full_access_when_no_secret_is_configured() {
+        let config = AuthConfig { secret: None };
+        let decoded = config.verify("anything", "room1", "alice").unwrap();
+        assert!(decoded.can_publish);
+        assert!(decoded.can_subscribe);
+    }
+
+    #[test]
+    fn may_publish_as_honors_delegated_sources() {
+        let mut g = grant("room1", "bridge");
+        g.can_publish_sources = vec!["alice".to_string()];
+        assert!(g.may_publish_as("bridge"));
+        assert!(g.may_publish_as("alice"));
+        assert!(!g.may_publish_as("bob"));
+    }
+}
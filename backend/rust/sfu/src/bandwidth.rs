@@ -0,0 +1,125 @@
+//! Receiver-side bandwidth estimation driving automatic simulcast layer
+//! selection.
+//!
+//! The full model this is adapted from (as described in the
+//! transport-wide-cc/GCC congestion-control drafts this SFU already
+//! negotiates the header extension for — see `crate::media_setup`) pairs a
+//! delay-based estimate — inter-group delay variation smoothed through a
+//! trendline/Kalman filter and compared against an adaptive threshold — with
+//! a loss-based one, and feeds the combined target bitrate into layer
+//! selection. The delay-based half, which parses `TransportLayerCc` feedback
+//! reports keyed to per-packet send timestamps, lives in
+//! `crate::congestion`. This module implements the loss-based half: classify a
+//! subscriber's reported loss into `Decrease`/`Hold`/`Increase` and step
+//! their target simulcast layer accordingly (see
+//! `crate::simulcast::{lower_rid, higher_rid}`), using the same
+//! settle-before-acting hysteresis `crate::quality::ConnectionQualityTracker`
+//! already applies to the connection-quality badge so a momentarily noisy
+//! link doesn't flap a subscriber between layers.
+
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Loss percentage above which a subscriber's target layer should be
+/// stepped down — mirrors GCC's loss-based multiplicative-decrease
+/// threshold.
+const LOSS_DECREASE_THRESHOLD_PCT: f32 = 10.0;
+
+/// Loss percentage below which a subscriber is eligible to step back up to
+/// a higher layer — mirrors GCC's loss-based additive-increase threshold.
+const LOSS_INCREASE_THRESHOLD_PCT: f32 = 2.0;
+
+/// How long a candidate step (up or down) must hold before it's acted on,
+/// so a momentary loss spike or a brief recovery doesn't flap the
+/// subscriber between layers on every Receiver Report.
+const HOLD_WINDOW: Duration = Duration::from_secs(3);
+
+/// The action a loss sample suggests for a subscriber's target simulcast
+/// layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerStep {
+    /// Loss is high enough that the subscriber should drop to the next
+    /// lower layer (see `crate::simulcast::lower_rid`).
+    Decrease,
+    /// Loss is in the band where the current layer should be left alone.
+    Hold,
+    /// Loss is low enough that the subscriber can try the next higher layer
+    /// (see `crate::simulcast::higher_rid`).
+    Increase,
+}
+
+fn classify(loss_pct: f32) -> LayerStep {
+    if loss_pct > LOSS_DECREASE_THRESHOLD_PCT {
+        LayerStep::Decrease
+    } else if loss_pct < LOSS_INCREASE_THRESHOLD_PCT {
+        LayerStep::Increase
+    } else {
+        LayerStep::Hold
+    }
+}
+
+struct State {
+    /// A candidate step, and when it was first observed; returned (and
+    /// cleared, so the next report starts a fresh hold) once it's held for
+    /// `HOLD_WINDOW`. Cleared whenever a differing candidate (including
+    /// `Hold`) arrives, so a flip in direction doesn't act on a stale hold.
+    /// Deliberately *not* deduplicated against whatever step was last acted
+    /// on: sustained high loss should keep stepping down one layer at a
+    /// time, once per `HOLD_WINDOW`, until the caller (which knows whether
+    /// there's a lower/higher layer left) stops asking.
+    pending: Option<(LayerStep, Instant)>,
+}
+
+/// Loss-based half of transport-wide congestion control for one
+/// subscriber's forwarding leg of a published track (see module docs).
+pub struct BandwidthEstimator {
+    state: Mutex<State>,
+}
+
+impl BandwidthEstimator {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State { pending: None }),
+        }
+    }
+
+    /// Folds in a fresh loss percentage sample (typically derived from an
+    /// RTCP Receiver Report's `fraction_lost`, the same one
+    /// `ConnectionQualityTracker::observe` is fed). Returns `Some(step)` the
+    /// instant a classification has held for `HOLD_WINDOW`, resetting the
+    /// hold so a caller that keeps reporting the same loss band keeps
+    /// getting stepped once per `HOLD_WINDOW` rather than only once — the
+    /// caller is the one that knows when there's no lower/higher layer left
+    /// to step to. `LayerStep::Hold` is never returned, since it's a no-op
+    /// by definition.
+    pub async fn observe(&self, loss_pct: f32) -> Option<LayerStep> {
+        let candidate = classify(loss_pct);
+        let mut state = self.state.lock().await;
+
+        if candidate == LayerStep::Hold {
+            state.pending = None;
+            return None;
+        }
+
+        match state.pending {
+            Some((step, since)) if step == candidate => {
+                if since.elapsed() >= HOLD_WINDOW {
+                    state.pending = None;
+                    Some(candidate)
+                } else {
+                    None
+                }
+            }
+            _ => {
+                state.pending = Some((candidate, Instant::now()));
+                None
+            }
+        }
+    }
+}
+
+impl Default for BandwidthEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
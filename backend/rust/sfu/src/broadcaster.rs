@@ -1,25 +1,417 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use tracing::info;
 use webrtc::peer_connection::RTCPeerConnection;
 use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use webrtc::rtp::packet::Packet;
 use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::rtp_transceiver::rtp_sender::RTCRtpSender;
+use webrtc::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocalWriter;
 
+use crate::quality::ConnectionQualityTracker;
+use crate::stats::{InboundRtpStats, OutboundRtpStats, OutboundRtpStatsSnapshot};
+
+/// Minimum spacing between PLIs sent to a single publisher. Concurrent
+/// `request_keyframe` callers inside this window are coalesced into the PLI
+/// the first caller already sent instead of each issuing their own — this is
+/// what keeps a burst of `add_writer` calls (several subscribers joining at
+/// once with no cached keyframe yet) down to one PLI instead of one per join.
+const PLI_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How many recently-sent packets each writer keeps around for NACK-driven
+/// retransmission. ~512 packets at typical video bitrates covers several
+/// hundred milliseconds, comfortably more than one RTT on most links.
+const NACK_BUFFER_CAPACITY: usize = 512;
+
+/// Depth of the per-broadcaster fan-out channel (see `TrackBroadcaster::new`
+/// and `broadcast`). Sized to absorb a burst of a few video frames' worth of
+/// packets so a writer's consumer task losing its scheduling slot for a
+/// moment doesn't immediately lag; a writer that falls further behind than
+/// this is by definition too slow to keep up and is better served by
+/// dropping to its next keyframe than by the publisher stalling for it.
+const BROADCAST_CHANNEL_CAPACITY: usize = 256;
+
+/// Default byte threshold for [`CoalesceConfig`], loosely adapting the
+/// `YIELD_THRESHOLD` buffering strategy tonic's encoder uses for its own
+/// write path: accumulate forwarded RTP payload bytes up to this much before
+/// flushing and yielding, instead of writing (and rescheduling) once per
+/// packet.
+const DEFAULT_COALESCE_BYTES_THRESHOLD: usize = 32 * 1024;
+
+/// Default flush deadline for [`CoalesceConfig`]: a writer flushes whatever
+/// it's buffered after this long even if `bytes_threshold` hasn't been
+/// reached, so a quiet track isn't held up waiting to fill the buffer.
+const DEFAULT_COALESCE_FLUSH_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Tunables for a [`BroadcasterWriter`]'s coalescing buffer (see
+/// `TrackBroadcaster::spawn_writer_consumer`). Operators configure this via
+/// environment variables so the same binary can trade latency for
+/// throughput depending on the deployment's typical publisher bitrate and
+/// worker count.
+#[derive(Debug, Clone, Copy)]
+pub struct CoalesceConfig {
+    /// Flush (and yield to the scheduler, since reaching this is what a
+    /// bursty publisher looks like) once this many payload bytes have
+    /// accumulated in a writer's buffer.
+    pub bytes_threshold: usize,
+    /// Flush whatever's buffered if this much time passes without reaching
+    /// `bytes_threshold`.
+    pub flush_interval: Duration,
+}
+
+impl Default for CoalesceConfig {
+    fn default() -> Self {
+        Self {
+            bytes_threshold: DEFAULT_COALESCE_BYTES_THRESHOLD,
+            flush_interval: DEFAULT_COALESCE_FLUSH_INTERVAL,
+        }
+    }
+}
+
+impl CoalesceConfig {
+    /// Reads `BROADCAST_COALESCE_BYTES` and `BROADCAST_COALESCE_FLUSH_MS`,
+    /// falling back to [`Default`] for either one that's unset or
+    /// unparsable.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let bytes_threshold = std::env::var("BROADCAST_COALESCE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.bytes_threshold);
+        let flush_interval = std::env::var("BROADCAST_COALESCE_FLUSH_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.flush_interval);
+        Self {
+            bytes_threshold,
+            flush_interval,
+        }
+    }
+}
+
+/// A bounded ring of recently-sent packets, keyed by the (already rewritten,
+/// subscriber-facing) RTP sequence number, so a `TransportLayerNack` can be
+/// answered by resending the exact packet instead of forcing a keyframe.
+///
+/// The resend itself goes out as an RTX packet (see `wrap_as_rtx`) when the
+/// primary payload type has a registered `apt` pairing (see
+/// `rtx_payload_type_for`/`MediaSetup::create_webrtc_api`), so the repair
+/// doesn't reuse the primary stream's own sequence space.
+pub struct NackBuffer {
+    packets: Mutex<VecDeque<(u16, Packet)>>,
+}
+
+impl NackBuffer {
+    pub fn new() -> Self {
+        Self {
+            packets: Mutex::new(VecDeque::with_capacity(NACK_BUFFER_CAPACITY)),
+        }
+    }
+
+    pub async fn record(&self, seq: u16, packet: Packet) {
+        let mut packets = self.packets.lock().await;
+        if packets.len() >= NACK_BUFFER_CAPACITY {
+            packets.pop_front();
+        }
+        packets.push_back((seq, packet));
+    }
+
+    pub async fn get(&self, seq: u16) -> Option<Packet> {
+        let packets = self.packets.lock().await;
+        packets
+            .iter()
+            .find(|(s, _)| *s == seq)
+            .map(|(_, p)| p.clone())
+    }
+}
+
+/// Maps a primary video payload type to its registered RTX (RFC 4588)
+/// payload type, mirroring the `apt=` pairings `MediaSetup::create_webrtc_api`
+/// registers (VP8 96↔97, H264 102↔103). `None` for anything without a
+/// registered pairing — audio is already excluded from retransmission
+/// entirely via `TrackBroadcaster::do_retransmission`.
+pub fn rtx_payload_type_for(primary_payload_type: u8) -> Option<u8> {
+    match primary_payload_type {
+        96 => Some(97),
+        102 => Some(103),
+        _ => None,
+    }
+}
+
+/// Wraps `original` as an RTX retransmission packet (RFC 4588): the 2-byte
+/// original sequence number is prepended to the payload, and the header's
+/// SSRC, payload type, and sequence number are swapped for the RTX stream's.
+///
+/// `webrtc-rs`'s `TrackLocalStaticRTP`/`add_track` doesn't expose negotiating
+/// an `a=ssrc-group:FID` association for a second, RTX-only SSRC on the same
+/// track, so `rtx_ssrc` isn't announced in the SDP the way a full RTX
+/// negotiation would be — this still produces a correctly-shaped RTX packet
+/// (right payload type, right OSN prefix) for any receiver that accepts RTX
+/// by payload type rather than a negotiated `ssrc-group`.
+pub fn wrap_as_rtx(
+    original: &Packet,
+    rtx_ssrc: u32,
+    rtx_payload_type: u8,
+    rtx_sequence_number: u16,
+) -> Packet {
+    let mut rtx = original.clone();
+    rtx.header.ssrc = rtx_ssrc;
+    rtx.header.payload_type = rtx_payload_type;
+    rtx.header.sequence_number = rtx_sequence_number;
+
+    let mut payload = bytes::BytesMut::with_capacity(2 + original.payload.len());
+    payload.extend_from_slice(&original.header.sequence_number.to_be_bytes());
+    payload.extend_from_slice(&original.payload);
+    rtx.payload = payload.freeze();
+
+    rtx
+}
+
 pub struct BroadcasterWriter {
+    /// Kept so a simulcast layer switch (`switch_subscriber_layer`) can hand
+    /// the same subscriber-facing local track to the new layer's
+    /// broadcaster rather than renegotiating a fresh one.
     pub track: Arc<TrackLocalStaticRTP>,
     pub ssrc: u32,
     pub payload_type: u8,
+    pub subscriber_user_id: String,
+    pub stats: Arc<OutboundRtpStats>,
+    /// Connection-quality hysteresis state for this subscriber's leg.
+    pub quality: Arc<ConnectionQualityTracker>,
+    /// The subscriber's PC and the sender this writer's track is attached
+    /// to, so muting can flip the downstream transceiver's direction
+    /// without tearing the track down.
+    pub downstream_pc: Arc<RTCPeerConnection>,
+    pub sender: Arc<RTCRtpSender>,
+    /// Recently-sent packets for this writer, consulted on NACK.
+    pub nack_buffer: Arc<NackBuffer>,
+    /// Delay-based congestion estimate for this subscriber's leg (see
+    /// `crate::congestion`), fed by `TransportLayerCc` feedback keyed to the
+    /// sequence numbers stamped via `twcc_extension_id`.
+    pub congestion: Arc<crate::congestion::CongestionController>,
+    /// This subscriber's negotiated id for the
+    /// `transport-wide-cc-extensions-01` header extension, if any. `None`
+    /// means the subscriber didn't negotiate it, so packets are forwarded
+    /// unstamped and `congestion` never receives feedback — the same
+    /// fail-open behavior `do_retransmission`/NACK already falls back to
+    /// when a capability isn't there.
+    pub twcc_extension_id: Option<u8>,
+    /// The independent consumer task reading this writer's subscription to
+    /// the broadcaster's fan-out channel (see `TrackBroadcaster::broadcast`).
+    /// Aborted when the writer is removed so it doesn't keep writing to a
+    /// track nothing references anymore.
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for BroadcasterWriter {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Clones `packet` (the payload is `Bytes`, so this is a cheap refcount
+/// bump), rewrites its SSRC/PT for this writer's subscriber, optionally
+/// stamps a transport-wide sequence number (see `crate::congestion`) onto
+/// the `transport-wide-cc-extensions-01` header extension at
+/// `twcc_stamp`'s id, pushes it onto `buffered`, and returns the payload
+/// bytes just added so the caller can track `buffered_bytes` against
+/// `CoalesceConfig::bytes_threshold`.
+fn rewrite_and_buffer(
+    packet: &Packet,
+    ssrc: u32,
+    payload_type: u8,
+    twcc_stamp: Option<(u8, u16)>,
+    buffered: &mut Vec<Packet>,
+) -> usize {
+    let mut packet_clone = packet.clone();
+    packet_clone.header.ssrc = ssrc;
+    if payload_type != 0 {
+        packet_clone.header.payload_type = payload_type;
+    }
+    if let Some((extension_id, seq)) = twcc_stamp {
+        packet_clone.header.extension = true;
+        let _ = packet_clone
+            .header
+            .set_extension(extension_id, bytes::Bytes::copy_from_slice(&seq.to_be_bytes()));
+    }
+    let bytes = packet_clone.payload.len();
+    buffered.push(packet_clone);
+    bytes
+}
+
+/// Whether `packet` should be dropped instead of forwarded because this
+/// writer's subscriber is congested (see `crate::congestion`). Always
+/// `false` for audio (`keyframe_gate` is `None`) or a frame that started
+/// with a keyframe packet, so congestion control never starves a decoder
+/// of the frame it needs to keep decoding at all.
+fn should_drop_for_congestion(
+    packet: &Packet,
+    congestion: &crate::congestion::CongestionController,
+    keyframe_gate: &mut Option<FrameKeyframeGate>,
+) -> bool {
+    let Some(gate) = keyframe_gate else {
+        return false;
+    };
+    // Observed on every packet, congested or not, so a keyframe whose first
+    // packet went by uncongested is still recognised as one.
+    let frame_started_with_keyframe = gate.observe(packet);
+    congestion.is_congested() && !frame_started_with_keyframe
+}
+
+/// Tracks whether the frame currently being forwarded (packets grouped by
+/// RTP timestamp, the same boundary `KeyframeCacheState` uses) started with
+/// a keyframe packet, so `TrackBroadcaster::spawn_writer_consumer` can drop
+/// whole non-keyframe frames under congestion without ever cutting a
+/// keyframe short.
+struct FrameKeyframeGate {
+    detector: Box<dyn crate::keyframe::KeyframeDetector>,
+    current_timestamp: Option<u32>,
+    current_is_keyframe: bool,
+}
+
+impl FrameKeyframeGate {
+    fn new(mime_type: &str) -> Self {
+        Self {
+            detector: crate::keyframe::detector_for_mime_type(mime_type),
+            current_timestamp: None,
+            current_is_keyframe: false,
+        }
+    }
+
+    /// Whether `packet` belongs to a frame that started with a keyframe
+    /// packet.
+    fn observe(&mut self, packet: &Packet) -> bool {
+        if Some(packet.header.timestamp) != self.current_timestamp {
+            self.current_timestamp = Some(packet.header.timestamp);
+            self.current_is_keyframe = self.detector.is_keyframe(&packet.payload);
+        }
+        self.current_is_keyframe
+    }
+}
+
+/// Writes every packet coalesced in `buffered` to `track`, in order, and
+/// drains it. One slow write here only delays this writer's own subsequent
+/// packets in the batch, not the publisher's ingest or any other writer.
+async fn flush_writer(
+    track: &Arc<TrackLocalStaticRTP>,
+    stats: &Arc<OutboundRtpStats>,
+    nack_buffer: &Arc<NackBuffer>,
+    buffered: &mut Vec<Packet>,
+) {
+    for packet in buffered.drain(..) {
+        if let Err(_e) = track.write_rtp(&packet).await {
+            // "Broken pipe" is common if the subscriber disconnected;
+            // `remove_writer`/session teardown will abort this task. Counted
+            // rather than ignored so a writer that's failing but not yet
+            // torn down shows up in `TrackBroadcaster::stats()`.
+            stats.record_send_failure();
+        } else {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64;
+            stats.record_forwarded(packet.payload.len(), now);
+            nack_buffer
+                .record(packet.header.sequence_number, packet)
+                .await;
+        }
+    }
+}
+
+/// Accumulates forwarded packets into the most recently completed keyframe,
+/// so a newly added writer can be fast-started with it (see
+/// `TrackBroadcaster::add_writer`) instead of waiting out a PLI round trip.
+///
+/// Packets sharing an RTP timestamp belong to the same frame, so a frame is
+/// considered complete (and promoted from `in_progress` to `cached`) once
+/// its marker bit closes it out. If a later packet's timestamp moves on
+/// without one, the partial frame in `in_progress` is discarded and the
+/// previously cached keyframe stays in place.
+struct KeyframeCacheState {
+    detector: Box<dyn crate::keyframe::KeyframeDetector>,
+    current_timestamp: Option<u32>,
+    in_progress: Vec<Packet>,
+    cached: Vec<Packet>,
+}
+
+impl KeyframeCacheState {
+    fn new(mime_type: &str) -> Self {
+        Self {
+            detector: crate::keyframe::detector_for_mime_type(mime_type),
+            current_timestamp: None,
+            in_progress: Vec::new(),
+            cached: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, packet: &Packet) {
+        if Some(packet.header.timestamp) != self.current_timestamp {
+            self.in_progress.clear();
+            self.current_timestamp = None;
+        }
+
+        if self.in_progress.is_empty() {
+            if !self.detector.is_keyframe(&packet.payload) {
+                return;
+            }
+            self.current_timestamp = Some(packet.header.timestamp);
+        }
+        self.in_progress.push(packet.clone());
+
+        if packet.header.marker {
+            self.cached = std::mem::take(&mut self.in_progress);
+            self.current_timestamp = None;
+        }
+    }
 }
 
 pub struct TrackBroadcaster {
     pub writers: Arc<RwLock<Vec<BroadcasterWriter>>>,
+    /// Fan-out channel the `read_rtp` loop publishes onto. Each writer
+    /// subscribes independently and runs its own consumer task (see
+    /// `add_writer`), so a slow/backpressured subscriber only lags its own
+    /// subscription instead of blocking the publish side or its siblings.
+    packet_tx: tokio::sync::broadcast::Sender<Arc<Packet>>,
     pub kind: String,
     pub capability: RTCRtpCodecCapability,
     pub source_pc: Arc<RTCPeerConnection>,
     pub source_ssrc: u32,
     pub last_keyframe_ts: Arc<std::sync::atomic::AtomicI64>,
+    // Timestamp of the last PLI sent (or currently being sent). Guards the
+    // debounce window so simultaneous `request_keyframe` callers coalesce
+    // into a single RTCP write.
+    last_pli_sent: Arc<Mutex<Option<Instant>>>,
+    /// Rolling inbound stats for the publisher's track this broadcaster
+    /// fans out, updated from the `read_rtp` loop in `track_handler`.
+    pub inbound_stats: Arc<InboundRtpStats>,
+    /// Whether this track's packets are currently forwarded to writers.
+    /// Flipped by `SetTrackEnabled` signaling so muting is O(1) and doesn't
+    /// disturb ICE/DTLS or require renegotiation.
+    enabled: Arc<AtomicBool>,
+    /// NTP/RTP timestamp pair (RFC 7273) from the publisher's most recent
+    /// inbound RTCP Sender Report, set by `record_clock_sync`.
+    clock_sync: Arc<Mutex<Option<(u64, u32)>>>,
+    /// Coalescing/yield tunables each writer's consumer task is spawned
+    /// with (see `spawn_writer_consumer`).
+    coalesce: CoalesceConfig,
+    /// Whether a `TransportLayerNack` for this track should be answered by
+    /// resending the cached packet. Audio generally isn't worth it — by the
+    /// time a retransmit would arrive it's usually past due for playout, so
+    /// callers pass `false` for audio tracks and let loss concealment handle
+    /// it instead.
+    do_retransmission: bool,
+    /// Cache of the most recently forwarded keyframe, keyed off `capability`
+    /// at construction time. A plain `std::sync::Mutex` is enough since it's
+    /// only ever held for the duration of one synchronous `record`/clone
+    /// call, never across an `.await`.
+    keyframe_cache: std::sync::Mutex<KeyframeCacheState>,
 }
 
 impl TrackBroadcaster {
@@ -29,22 +421,166 @@ impl TrackBroadcaster {
         source_pc: Arc<RTCPeerConnection>,
         source_ssrc: u32,
     ) -> Self {
+        Self::with_coalesce_config(
+            kind,
+            capability,
+            source_pc,
+            source_ssrc,
+            CoalesceConfig::default(),
+            true,
+        )
+    }
+
+    /// Like [`TrackBroadcaster::new`], but with explicit coalescing/yield
+    /// tunables instead of [`CoalesceConfig::default`] — used in production
+    /// to plug in [`CoalesceConfig::from_env`] — and an explicit
+    /// `do_retransmission` (see the field doc on [`TrackBroadcaster`]).
+    pub fn with_coalesce_config(
+        kind: String,
+        capability: RTCRtpCodecCapability,
+        source_pc: Arc<RTCPeerConnection>,
+        source_ssrc: u32,
+        coalesce: CoalesceConfig,
+        do_retransmission: bool,
+    ) -> Self {
+        let clock_rate = capability.clock_rate;
+        let keyframe_cache = std::sync::Mutex::new(KeyframeCacheState::new(&capability.mime_type));
+        let (packet_tx, _) = tokio::sync::broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         Self {
             writers: Arc::new(RwLock::new(Vec::new())),
+            packet_tx,
             kind,
             capability,
             source_pc,
             source_ssrc,
             last_keyframe_ts: Arc::new(std::sync::atomic::AtomicI64::new(0)),
+            last_pli_sent: Arc::new(Mutex::new(None)),
+            inbound_stats: Arc::new(InboundRtpStats::new(clock_rate)),
+            enabled: Arc::new(AtomicBool::new(true)),
+            clock_sync: Arc::new(Mutex::new(None)),
+            coalesce,
+            do_retransmission,
+            keyframe_cache,
         }
     }
 
-    pub async fn add_writer(&self, writer: Arc<TrackLocalStaticRTP>, ssrc: u32, payload_type: u8) {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Subscribes to this broadcaster's raw fan-out channel directly,
+    /// bypassing the per-writer SSRC/PT rewrite. Used by
+    /// `crate::captions` to tap a publisher's audio for transcription
+    /// without standing up a `BroadcasterWriter`/local track for it.
+    pub fn subscribe_packets(&self) -> tokio::sync::broadcast::Receiver<Arc<Packet>> {
+        self.packet_tx.subscribe()
+    }
+
+    /// Whether a NACK for a packet this broadcaster forwarded should be
+    /// answered by resending it from the writer's cache (see
+    /// `do_retransmission` on [`TrackBroadcaster`]).
+    pub fn do_retransmission(&self) -> bool {
+        self.do_retransmission
+    }
+
+    /// Records the NTP↔RTP pair from the publisher's latest inbound Sender
+    /// Report. Returns `true` only the first time this is called for this
+    /// broadcaster, so callers emit a `ClockSyncEvent` once rather than on
+    /// every SR (sent roughly every few seconds for the life of the track).
+    pub async fn record_clock_sync(&self, ntp_time: u64, rtp_time: u32) -> bool {
+        let mut guard = self.clock_sync.lock().await;
+        let first = guard.is_none();
+        *guard = Some((ntp_time, rtp_time));
+        first
+    }
+
+    /// Mutes/unmutes this track in place: flips the cheap forwarding flag
+    /// (`is_enabled`, which the `track_handler` read loop checks before
+    /// calling `broadcast`), and flips every current writer's downstream
+    /// transceiver direction so the subscriber's UA sees the change too.
+    pub async fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+
+        let direction = if enabled {
+            RTCRtpTransceiverDirection::Sendonly
+        } else {
+            RTCRtpTransceiverDirection::Inactive
+        };
+
+        let writers = self.writers.read().await;
+        for w in writers.iter() {
+            for transceiver in w.downstream_pc.get_transceivers().await {
+                if Arc::ptr_eq(&transceiver.sender().await, &w.sender) {
+                    transceiver.set_direction(direction);
+                    break;
+                }
+            }
+        }
+    }
+
+    pub async fn add_writer(
+        self: &Arc<Self>,
+        writer: Arc<TrackLocalStaticRTP>,
+        ssrc: u32,
+        payload_type: u8,
+        subscriber_user_id: String,
+        stats: Arc<OutboundRtpStats>,
+        quality: Arc<ConnectionQualityTracker>,
+        downstream_pc: Arc<RTCPeerConnection>,
+        sender: Arc<RTCRtpSender>,
+        nack_buffer: Arc<NackBuffer>,
+        congestion: Arc<crate::congestion::CongestionController>,
+        twcc_extension_id: Option<u8>,
+    ) {
+        // Fast-start: replay the last cached keyframe (already rewritten for
+        // this writer) before it ever subscribes to the live feed, so it
+        // doesn't have to sit through a PLI round trip to decode anything.
+        // Only fall back to asking the publisher for one if there's nothing
+        // cached yet.
+        let cached_keyframe = self.cached_keyframe();
+        let replayed_keyframe = !cached_keyframe.is_empty();
+        if replayed_keyframe {
+            let mut buffered = Vec::with_capacity(cached_keyframe.len());
+            for packet in &cached_keyframe {
+                let twcc_stamp = twcc_extension_id.map(|id| (id, congestion.next_sequence_number()));
+                rewrite_and_buffer(packet, ssrc, payload_type, twcc_stamp, &mut buffered);
+                if let Some((_, seq)) = twcc_stamp {
+                    congestion.record_sent(seq).await;
+                }
+            }
+            flush_writer(&writer, &stats, &nack_buffer, &mut buffered).await;
+            info!(
+                subscriber_user_id = %subscriber_user_id,
+                packets = cached_keyframe.len(),
+                "[SFU] Replayed cached keyframe to new writer"
+            );
+        }
+
+        let task = self.spawn_writer_consumer(
+            writer.clone(),
+            ssrc,
+            payload_type,
+            subscriber_user_id.clone(),
+            stats.clone(),
+            nack_buffer.clone(),
+            congestion.clone(),
+            twcc_extension_id,
+        );
+
         let mut writers = self.writers.write().await;
         writers.push(BroadcasterWriter {
             track: writer,
             ssrc,
             payload_type,
+            subscriber_user_id,
+            stats,
+            quality,
+            downstream_pc,
+            sender,
+            nack_buffer,
+            congestion,
+            twcc_extension_id,
+            task,
         });
         info!(
             kind = %self.kind,
@@ -52,12 +588,162 @@ impl TrackBroadcaster {
             payload_type = %payload_type,
             "[SFU] Added writer for track"
         );
-        // We use schedule_pli_retry when adding a writer now?
-        // No, add_writer calls request_keyframe() originally.
-        // The burst was called externally.
-        // I'll leave request_keyframe() here or remove it if schedule_pli_retry does it.
-        // Original add_writer called request_keyframe().
-        self.request_keyframe().await;
+
+        if !replayed_keyframe {
+            // Nothing cached yet (e.g. the publisher hasn't sent a keyframe
+            // since this broadcaster started, or this is an audio track):
+            // ask for one so the new writer isn't stuck waiting for the next
+            // scheduled one.
+            self.request_keyframe().await;
+        }
+    }
+
+    /// Spawns the independent task that delivers this writer's share of the
+    /// fan-out: subscribes to `packet_tx`, rewrites SSRC/PT per packet, and
+    /// writes to `track`. Runs until the channel closes (broadcaster
+    /// dropped) or the task is aborted (writer removed/moved).
+    ///
+    /// Packets are coalesced into a buffer (see `self.coalesce`) instead of
+    /// writing and rescheduling once per packet: after the first packet of a
+    /// burst, the task keeps pulling whatever arrives until either
+    /// `flush_interval` elapses or `bytes_threshold` is crossed, then
+    /// flushes the whole buffer in one
+    /// tight loop. Reaching `bytes_threshold` is exactly what a single noisy
+    /// publisher monopolizing this worker looks like, so that case also
+    /// yields to the scheduler once the flush is done.
+    fn spawn_writer_consumer(
+        self: &Arc<Self>,
+        track: Arc<TrackLocalStaticRTP>,
+        ssrc: u32,
+        payload_type: u8,
+        subscriber_user_id: String,
+        stats: Arc<OutboundRtpStats>,
+        nack_buffer: Arc<NackBuffer>,
+        congestion: Arc<crate::congestion::CongestionController>,
+        twcc_extension_id: Option<u8>,
+    ) -> tokio::task::JoinHandle<()> {
+        let mut rx = self.packet_tx.subscribe();
+        let broadcaster = self.clone();
+        let coalesce = self.coalesce;
+        // Only video gets dropped under congestion: audio's bitrate is
+        // negligible next to video's, and a dropped audio frame degrades
+        // the call far more per byte saved than a dropped delta frame does.
+        let mut keyframe_gate = (broadcaster.kind == "video")
+            .then(|| FrameKeyframeGate::new(&broadcaster.capability.mime_type));
+        tokio::spawn(async move {
+            let mut buffered: Vec<Packet> = Vec::new();
+            let mut buffered_bytes = 0usize;
+            'outer: loop {
+                let packet = match rx.recv().await {
+                    Ok(packet) => packet,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            subscriber = %subscriber_user_id,
+                            skipped,
+                            "[SFU] Writer fell behind the publisher; dropping packets and requesting a keyframe"
+                        );
+                        broadcaster.request_keyframe().await;
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if should_drop_for_congestion(&packet, &congestion, &mut keyframe_gate) {
+                    continue;
+                }
+                let twcc_stamp = twcc_extension_id.map(|id| (id, congestion.next_sequence_number()));
+                buffered_bytes +=
+                    rewrite_and_buffer(&packet, ssrc, payload_type, twcc_stamp, &mut buffered);
+                if let Some((_, seq)) = twcc_stamp {
+                    congestion.record_sent(seq).await;
+                }
+
+                // Keep coalescing whatever else is ready (or arrives within
+                // the flush deadline) instead of flushing this single packet
+                // right away.
+                let deadline = tokio::time::sleep(coalesce.flush_interval);
+                tokio::pin!(deadline);
+                while buffered_bytes < coalesce.bytes_threshold {
+                    tokio::select! {
+                        biased;
+                        res = rx.recv() => match res {
+                            Ok(packet) => {
+                                if should_drop_for_congestion(&packet, &congestion, &mut keyframe_gate) {
+                                    continue;
+                                }
+                                let twcc_stamp =
+                                    twcc_extension_id.map(|id| (id, congestion.next_sequence_number()));
+                                buffered_bytes +=
+                                    rewrite_and_buffer(&packet, ssrc, payload_type, twcc_stamp, &mut buffered);
+                                if let Some((_, seq)) = twcc_stamp {
+                                    congestion.record_sent(seq).await;
+                                }
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                                tracing::warn!(
+                                    subscriber = %subscriber_user_id,
+                                    skipped,
+                                    "[SFU] Writer fell behind the publisher; dropping packets and requesting a keyframe"
+                                );
+                                broadcaster.request_keyframe().await;
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                                flush_writer(&track, &stats, &nack_buffer, &mut buffered).await;
+                                break 'outer;
+                            }
+                        },
+                        _ = &mut deadline => break,
+                    }
+                }
+
+                let flushed_bytes = buffered_bytes;
+                flush_writer(&track, &stats, &nack_buffer, &mut buffered).await;
+                buffered_bytes = 0;
+
+                if flushed_bytes >= coalesce.bytes_threshold {
+                    tokio::task::yield_now().await;
+                }
+            }
+        })
+    }
+
+    /// Removes and returns the writer forwarding to `subscriber_user_id`, if
+    /// any, so its `track` and bookkeeping can be re-attached to a different
+    /// layer's broadcaster via `add_writer` (see `switch_subscriber_layer`).
+    /// Dropping the returned value aborts its old consumer task; the caller
+    /// should not keep using it to write.
+    pub async fn remove_writer(&self, subscriber_user_id: &str) -> Option<BroadcasterWriter> {
+        let mut writers = self.writers.write().await;
+        let pos = writers
+            .iter()
+            .position(|w| w.subscriber_user_id == subscriber_user_id)?;
+        Some(writers.remove(pos))
+    }
+
+    /// Snapshots every current writer's `OutboundRtpStats`, keyed by
+    /// `subscriber_user_id`, so callers (e.g. an adaptive-dropping policy, or
+    /// a debug endpoint) can see per-subscriber forwarding health without
+    /// reaching into `writers` themselves. `collect_track_stats` in
+    /// `crate::sfu_service` is the existing consumer of this same data for
+    /// `GetStats`/`StatsSnapshot`; this is the lower-level building block it
+    /// could be rebuilt on top of.
+    pub async fn stats(&self) -> HashMap<String, OutboundRtpStatsSnapshot> {
+        self.writers
+            .read()
+            .await
+            .iter()
+            .map(|w| (w.subscriber_user_id.clone(), w.stats.snapshot()))
+            .collect()
+    }
+
+    /// Whether this broadcaster currently has a writer forwarding to
+    /// `subscriber_user_id`, i.e. whether it's that subscriber's current
+    /// simulcast layer.
+    pub async fn has_writer(&self, subscriber_user_id: &str) -> bool {
+        self.writers
+            .read()
+            .await
+            .iter()
+            .any(|w| w.subscriber_user_id == subscriber_user_id)
     }
 
     pub fn mark_keyframe_received(&self) {
@@ -67,6 +753,7 @@ impl TrackBroadcaster {
             .as_millis() as i64;
         self.last_keyframe_ts
             .store(now, std::sync::atomic::Ordering::Relaxed);
+        self.inbound_stats.mark_keyframe_received(now);
     }
 
     pub async fn request_keyframe(&self) {
@@ -74,6 +761,24 @@ impl TrackBroadcaster {
             return;
         }
 
+        // Single-flight: whoever observes no recent/in-flight PLI claims the
+        // slot by stamping `last_pli_sent` before it actually writes the
+        // RTCP packet, so every other concurrent caller within the debounce
+        // window sees a recent timestamp and returns without sending.
+        {
+            let mut last_sent = self.last_pli_sent.lock().await;
+            if let Some(ts) = *last_sent {
+                if ts.elapsed() < PLI_DEBOUNCE {
+                    tracing::debug!(
+                        source_ssrc = %self.source_ssrc,
+                        "[SFU] Coalescing keyframe request into in-flight/recent PLI"
+                    );
+                    return;
+                }
+            }
+            *last_sent = Some(Instant::now());
+        }
+
         info!(source_ssrc = %self.source_ssrc, "[SFU] Requesting keyframe");
         let pli = PictureLossIndication {
             sender_ssrc: 0,
@@ -110,25 +815,38 @@ impl TrackBroadcaster {
         });
     }
 
-    /// Optimized broadcast loop: clones packet only when necessary (modifying SSRC/PT)
-    /// and avoids deep cloning of payload if we can help it (though helper writes usually take &Packet).
-    pub async fn broadcast(&self, packet: &mut webrtc::rtp::packet::Packet) {
-        let writers = self.writers.read().await;
-        for w in writers.iter() {
-            // We must modify SSRC and PT for the outgoing track.
-            // Writing to TrackLocalStaticRTP usually takes a reference, but since we modify header,
-            // we have to clone the packet header at least. Payload is Bytes, so cloning it is cheap (Arc logic).
-
-            let mut packet_clone = packet.clone();
-            packet_clone.header.ssrc = w.ssrc;
-            if w.payload_type != 0 {
-                packet_clone.header.payload_type = w.payload_type;
-            }
-
-            if let Err(_e) = w.track.write_rtp(&packet_clone).await {
-                // debug!(error = %_e, "Error forwarding packet");
-                // "Broken pipe" is common if peer disconnected
+    /// Publishes one packet onto the fan-out channel: a single clone (the
+    /// payload is `Bytes`, so this is a cheap refcount bump) and a send, with
+    /// no awaiting on any writer. Each writer's own consumer task (spawned
+    /// in `add_writer`) picks the packet up on its own schedule, rewrites
+    /// its SSRC/PT, and writes it — so one slow/backpressured subscriber
+    /// can't stall delivery to the rest of the room, and ingest never blocks
+    /// on a downstream write.
+    ///
+    /// Per writer, only the SSRC and payload type are rewritten and, where
+    /// the subscriber negotiated it, the transport-wide-cc sequence number
+    /// is stamped; other header extensions — including `abs-capture-time`,
+    /// when the publisher sends
+    /// it — are forwarded untouched so subscribers can align this stream to
+    /// the reference clock advertised in the session's `a=ts-refclk`.
+    pub fn broadcast(&self, packet: &webrtc::rtp::packet::Packet) {
+        if self.kind == "video" {
+            if let Ok(mut cache) = self.keyframe_cache.lock() {
+                cache.record(packet);
             }
         }
+        // No writers yet (or none left) is not an error: `send` only fails
+        // when there are zero receivers, which just means nobody's
+        // subscribed to this layer right now.
+        let _ = self.packet_tx.send(Arc::new(packet.clone()));
+    }
+
+    /// Returns the most recently completed keyframe's packets, in wire
+    /// order, if one has been cached yet (see `KeyframeCacheState`).
+    fn cached_keyframe(&self) -> Vec<Packet> {
+        self.keyframe_cache
+            .lock()
+            .map(|cache| cache.cached.clone())
+            .unwrap_or_default()
     }
 }
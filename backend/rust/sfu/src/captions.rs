@@ -0,0 +1,207 @@
+//! Live captioning: taps a publisher's audio `TrackBroadcaster`, depacketizes
+//! its Opus RTP payload, and streams it to an external `cc.CaptioningService`
+//! over gRPC, relaying the transcriptions it returns back into the room as a
+//! `Caption` event (see `crate::pb::sfu::sfu_event::Payload::Caption`).
+//!
+//! This forwards the depacketized Opus frame as-is rather than decoding it
+//! to PCM: most captioning backends accept compressed Opus directly, and
+//! decoding would mean pulling in an audio-codec decoder dependency this SFU
+//! otherwise has no use for. Audio never blocks on the captioner: frames are
+//! handed to the outbound gRPC stream through a bounded channel via
+//! `try_send`, so a slow or disconnected captioning backend drops audio
+//! instead of stalling the broadcast loop it's tapping.
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, info, warn};
+use webrtc::rtp::codecs::opus::OpusPacket;
+use webrtc::rtp::packetizer::Depacketizer;
+
+use crate::broadcaster::TrackBroadcaster;
+use crate::pb;
+use crate::types::PeerMap;
+
+/// How many depacketized Opus frames to accumulate per `AudioChunk`: 20ms
+/// frames batched into ~1s chunks, so the captioner gets a reasonable amount
+/// of context per call instead of a round-trip every 20ms.
+const CHUNK_FRAME_COUNT: usize = 50;
+
+/// How long to wait before retrying a dropped/failed connection to the
+/// captioning service.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Depth of the bounded channel between the RTP tap and the outbound gRPC
+/// stream. Kept small — this is a live feed, not a buffer a backlog should
+/// accumulate in.
+const OUTBOUND_CHANNEL_CAPACITY: usize = 8;
+
+/// Where to reach the captioning backend. Disabled (the default) unless
+/// `SFU_CAPTIONING_ENDPOINT` is set, since most deployments don't run one.
+pub struct CaptioningConfig {
+    pub endpoint: Option<String>,
+}
+
+impl CaptioningConfig {
+    pub fn from_env() -> Self {
+        Self {
+            endpoint: std::env::var("SFU_CAPTIONING_ENDPOINT")
+                .ok()
+                .filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+/// If `config` has a captioning endpoint configured and `broadcaster` is an
+/// audio track, spawns a background task that streams its audio to the
+/// captioning service and relays transcriptions back to every peer in the
+/// room. No-op for video tracks or when captioning isn't configured.
+pub fn maybe_spawn(
+    config: &CaptioningConfig,
+    broadcaster: &Arc<TrackBroadcaster>,
+    room_id: String,
+    user_id: String,
+    stream_id: String,
+    peers: PeerMap,
+) {
+    if broadcaster.kind != "audio" {
+        return;
+    }
+    let Some(endpoint) = config.endpoint.clone() else {
+        return;
+    };
+
+    let session_id = format!("{}:{}:{}", room_id, user_id, stream_id);
+    let broadcaster = Arc::downgrade(broadcaster);
+    tokio::spawn(async move {
+        run_captioning_loop(endpoint, broadcaster, room_id, user_id, session_id, peers).await;
+    });
+}
+
+/// Reconnects to the captioning service for as long as the publisher's
+/// `TrackBroadcaster` is still alive — each iteration tears down and rebuilds
+/// the bidi stream so a captioner restart doesn't permanently stop
+/// transcription for a long-lived call. Holds only a `Weak` reference to the
+/// broadcaster so this task doesn't itself keep a departed publisher's track
+/// alive; once the broadcaster is gone, the loop exits instead of
+/// reconnecting forever.
+async fn run_captioning_loop(
+    endpoint: String,
+    broadcaster: Weak<TrackBroadcaster>,
+    room_id: String,
+    user_id: String,
+    session_id: String,
+    peers: PeerMap,
+) {
+    loop {
+        let Some(strong) = broadcaster.upgrade() else {
+            debug!(%session_id, "[Captions] Publisher track gone, stopping captioning");
+            return;
+        };
+        let mut packet_rx = strong.subscribe_packets();
+        drop(strong);
+
+        let mut client = match pb::cc::captioning_service_client::CaptioningServiceClient::connect(
+            endpoint.clone(),
+        )
+        .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                warn!(error = %e, %session_id, "[Captions] Failed to connect to captioning service, retrying");
+                tokio::time::sleep(RECONNECT_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let (tx, rx) = mpsc::channel::<pb::cc::AudioChunk>(OUTBOUND_CHANNEL_CAPACITY);
+
+        let response = match client.stream_audio(ReceiverStream::new(rx)).await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, %session_id, "[Captions] stream_audio call failed, retrying");
+                tokio::time::sleep(RECONNECT_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let sender_session_id = session_id.clone();
+        let sender_task = tokio::spawn(async move {
+            let mut depacketizer = OpusPacket::default();
+            let mut pending_frames: Vec<bytes::Bytes> = Vec::with_capacity(CHUNK_FRAME_COUNT);
+            loop {
+                match packet_rx.recv().await {
+                    Ok(packet) => {
+                        let frame = match depacketizer.depacketize(&packet.payload) {
+                            Ok(f) => f,
+                            Err(e) => {
+                                debug!(error = %e, "[Captions] Failed to depacketize Opus payload");
+                                continue;
+                            }
+                        };
+                        pending_frames.push(frame);
+                        if pending_frames.len() < CHUNK_FRAME_COUNT {
+                            continue;
+                        }
+
+                        let mut audio_data = Vec::new();
+                        for f in pending_frames.drain(..) {
+                            audio_data.extend_from_slice(&f);
+                        }
+                        let chunk = pb::cc::AudioChunk {
+                            session_id: sender_session_id.clone(),
+                            audio_data,
+                        };
+                        // Drop rather than block: a backpressured captioner
+                        // shouldn't stall this broadcaster's fan-out.
+                        if tx.try_send(chunk).is_err() {
+                            debug!(session_id = %sender_session_id, "[Captions] Dropping audio chunk, captioner backpressured");
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
+        });
+
+        let mut inbound = response.into_inner();
+        loop {
+            match inbound.message().await {
+                Ok(Some(event)) => emit_caption(&peers, &room_id, &user_id, event).await,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(error = %e, %session_id, "[Captions] Caption stream error");
+                    break;
+                }
+            }
+        }
+
+        sender_task.abort();
+        info!(%session_id, "[Captions] Captioning stream ended, reconnecting");
+        tokio::time::sleep(RECONNECT_BACKOFF).await;
+    }
+}
+
+/// Relays a transcription to every peer in the room as a `Caption` event.
+async fn emit_caption(
+    peers: &PeerMap,
+    room_id: &str,
+    target_user_id: &str,
+    event: pb::cc::CaptionEvent,
+) {
+    let payload = pb::sfu::sfu_event::Payload::Caption(pb::signaling::CaptionEvent {
+        user_id: target_user_id.to_string(),
+        text: event.text,
+        is_final: event.is_final,
+        confidence: event.confidence,
+    });
+
+    for peer_entry in peers.iter() {
+        let peer = peer_entry.value();
+        if peer.room_id == room_id {
+            peer.event_tx.emit(payload.clone()).await;
+        }
+    }
+}
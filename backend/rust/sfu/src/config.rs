@@ -8,6 +8,8 @@ pub struct Config {
     pub grpc_port: u16,
     /// Port for the HTTP metrics server (Prometheus).
     pub metrics_port: u16,
+    /// Port for the WHIP/WHEP HTTP ingest/egress router (`crate::whip_whep::router`).
+    pub whip_port: u16,
     /// Logging level (e.g., "info", "debug").
     pub rust_log: String,
     /// Address of the captioning service
@@ -46,6 +48,46 @@ impl std::fmt::Display for ConfigError {
 
 impl std::error::Error for ConfigError {}
 
+/// Floor for a subscriber's `crate::congestion::CongestionController`
+/// target bitrate, below which multiplicative decrease stops reducing it
+/// further. Overridden by `MIN_BITRATE_BPS`.
+const DEFAULT_MIN_BITRATE_BPS: u64 = 50_000;
+
+/// Ceiling for a subscriber's target bitrate. Overridden by
+/// `MAX_BITRATE_BPS`.
+const DEFAULT_MAX_BITRATE_BPS: u64 = 8_000_000;
+
+/// `MIN_BITRATE_BPS` (bits per second), defaulting to
+/// [`DEFAULT_MIN_BITRATE_BPS`] if unset or unparseable.
+pub fn min_bitrate_bps() -> u64 {
+    env::var("MIN_BITRATE_BPS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MIN_BITRATE_BPS)
+}
+
+/// `MAX_BITRATE_BPS` (bits per second), defaulting to
+/// [`DEFAULT_MAX_BITRATE_BPS`] if unset or unparseable.
+pub fn max_bitrate_bps() -> u64 {
+    env::var("MAX_BITRATE_BPS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_BITRATE_BPS)
+}
+
+/// Ceiling on distinct users per room, checked by `create_session` via
+/// `MySfu::room_manager`. Overridden by `MAX_PARTICIPANTS_PER_ROOM`.
+const DEFAULT_MAX_PARTICIPANTS_PER_ROOM: usize = 50;
+
+/// `MAX_PARTICIPANTS_PER_ROOM`, defaulting to
+/// [`DEFAULT_MAX_PARTICIPANTS_PER_ROOM`] if unset or unparseable.
+pub fn max_participants_per_room() -> usize {
+    env::var("MAX_PARTICIPANTS_PER_ROOM")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_PARTICIPANTS_PER_ROOM)
+}
+
 /// Validates environment variables and returns a Config object
 /// Returns an error if any required variable is missing or invalid
 pub fn validate_env() -> Result<Config, ConfigError> {
@@ -79,9 +121,16 @@ pub fn validate_env() -> Result<Config, ConfigError> {
         .parse()
         .map_err(|e| ConfigError::InvalidPort("METRICS_PORT".to_string(), e))?;
 
+    // Optional: WHIP_PORT (defaults to 8080)
+    let whip_port: u16 = env::var("WHIP_PORT")
+        .unwrap_or_else(|_| "8080".to_string())
+        .parse()
+        .map_err(|e| ConfigError::InvalidPort("WHIP_PORT".to_string(), e))?;
+
     let config = Config {
         grpc_port,
         metrics_port,
+        whip_port,
         rust_log,
         cc_service_addr,
     };
@@ -155,6 +204,16 @@ mod tests {
         assert_eq!(config.metrics_port, 9090);
     }
 
+    #[test]
+    fn test_validate_env_whip_port() {
+        let mut guard = EnvGuard::new();
+        guard.set("GRPC_PORT", "50051");
+        guard.set("WHIP_PORT", "8443");
+
+        let config = validate_env().expect("Expected valid configuration");
+        assert_eq!(config.whip_port, 8443);
+    }
+
     #[test]
     fn test_validate_env_invalid_metrics_port() {
         let mut guard = EnvGuard::new();
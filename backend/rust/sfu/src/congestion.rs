@@ -0,0 +1,260 @@
+//! Delay-based half of transport-wide congestion control, complementing
+//! the loss-based layer stepping in [`crate::bandwidth`].
+//!
+//! Each packet a `BroadcasterWriter` forwards is stamped (see
+//! `crate::broadcaster::rewrite_and_buffer`) with a 16-bit transport-wide
+//! sequence number carried in the `transport-wide-cc-extensions-01` header
+//! extension the media engine already negotiates (see
+//! `crate::media_setup`). `record_sent` notes the local send time for that
+//! sequence number; when the subscriber reflects it back in a
+//! `TransportLayerCc` RTCP feedback report, `on_feedback` pairs the two up
+//! and turns consecutive (arrival delta - departure delta) pairs into a
+//! one-way delay gradient. A smoothed-threshold over-use detector on that
+//! gradient, combined with reported loss, drives AIMD on a target bitrate:
+//! multiplicative decrease on over-use or high loss, additive increase
+//! otherwise, clamped to `MIN_BITRATE_BPS`/`MAX_BITRATE_BPS` (see
+//! `crate::config::min_bitrate_bps`/`crate::config::max_bitrate_bps`).
+//!
+//! A subscriber that never negotiates the extension (or whose browser
+//! doesn't send feedback) simply never calls `on_feedback`, so
+//! `target_bitrate_bps` stays at its generous initial value rather than
+//! throttling a link this module has no visibility into — the same
+//! fail-open posture `crate::bandwidth::BandwidthEstimator` already takes
+//! when no Receiver Reports arrive.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+use webrtc::rtcp::transport_feedbacks::transport_layer_cc::{
+    PacketStatusChunk, SymbolTypeTcc, TransportLayerCc,
+};
+
+/// Starting target bitrate before the first feedback report arrives,
+/// deliberately generous so a fresh subscriber isn't throttled before this
+/// controller has any signal to act on.
+const INITIAL_BITRATE_BPS: u64 = 1_000_000;
+
+/// Multiplicative decrease applied to the target bitrate on detected
+/// over-use or high loss, mirroring GCC's AIMD controller.
+const DECREASE_FACTOR: f64 = 0.85;
+
+/// Additive increase applied once per feedback report when neither
+/// over-use nor high loss was detected.
+const INCREASE_STEP_BPS: u64 = 50_000;
+
+/// Loss percentage above which feedback forces a decrease even without a
+/// delay-based over-use signal; same threshold
+/// `crate::bandwidth::LOSS_DECREASE_THRESHOLD_PCT` uses for layer stepping.
+const LOSS_DECREASE_THRESHOLD_PCT: f32 = 10.0;
+
+/// How far the smoothed delay gradient (milliseconds) has to drift above
+/// zero before it counts as sustained over-use rather than ordinary
+/// jitter.
+const OVERUSE_THRESHOLD_MS: f64 = 15.0;
+
+/// EWMA smoothing factor for the delay gradient; same shape as the jitter
+/// estimate in `crate::stats::InboundRtpStats::record_arrival`.
+const GRADIENT_SMOOTHING: f64 = 1.0 / 16.0;
+
+/// How long a `record_sent` entry waits for matching feedback before it's
+/// pruned, so a subscriber that stops sending TWCC reports doesn't leak
+/// the send-time map.
+const SENT_ENTRY_TTL: Duration = Duration::from_secs(5);
+
+/// Prune pass kicks in once the map grows past this many outstanding
+/// entries, rather than checking TTLs on every insert.
+const SENT_MAP_PRUNE_THRESHOLD: usize = 4096;
+
+struct State {
+    sent: HashMap<u16, Instant>,
+    /// Local send time of the most recently feedback-matched packet, so
+    /// the next one's departure delta is relative to it rather than to an
+    /// absolute clock the subscriber doesn't share.
+    last_departure: Option<Instant>,
+    smoothed_gradient_ms: f64,
+}
+
+/// Delay-based congestion estimator for one subscriber's forwarding leg of
+/// a published track (see module docs).
+pub struct CongestionController {
+    state: Mutex<State>,
+    next_sequence_number: AtomicU16,
+    target_bitrate_bps: AtomicU64,
+    /// Whether the most recent AIMD step was a decrease, i.e. the last
+    /// feedback report this controller folded in showed over-use or high
+    /// loss. Consulted by `crate::broadcaster` as a coarse "drop
+    /// non-keyframe frames" gate instead of tracking a separate
+    /// instantaneous send rate.
+    congested: AtomicBool,
+    min_bitrate_bps: u64,
+    max_bitrate_bps: u64,
+}
+
+/// Expands a `TransportLayerCc` report's run-length/status-vector chunks
+/// into one [`SymbolTypeTcc`] per packet covered by the report, in sequence
+/// order starting at `base_sequence_number`. `feedback.recv_deltas` only has
+/// one entry per packet whose expanded status isn't `PacketNotReceived`, so
+/// callers must walk this alongside `recv_deltas` rather than assume the two
+/// line up index-for-index with a contiguous sequence range.
+fn expand_packet_statuses(chunks: &[PacketStatusChunk], packet_status_count: u16) -> Vec<SymbolTypeTcc> {
+    let mut statuses = Vec::with_capacity(packet_status_count as usize);
+    for chunk in chunks {
+        if statuses.len() >= packet_status_count as usize {
+            break;
+        }
+        match chunk {
+            PacketStatusChunk::RunLengthChunk(run) => {
+                let remaining = packet_status_count as usize - statuses.len();
+                let count = (run.run_length as usize).min(remaining);
+                statuses.extend(std::iter::repeat(run.packet_status_symbol).take(count));
+            }
+            PacketStatusChunk::StatusVectorChunk(vector) => {
+                statuses.extend(vector.symbol_list.iter().copied());
+            }
+        }
+    }
+    statuses.truncate(packet_status_count as usize);
+    statuses
+}
+
+impl CongestionController {
+    pub fn new(min_bitrate_bps: u64, max_bitrate_bps: u64) -> Self {
+        let min_bitrate_bps = min_bitrate_bps.min(max_bitrate_bps);
+        Self {
+            state: Mutex::new(State {
+                sent: HashMap::new(),
+                last_departure: None,
+                smoothed_gradient_ms: 0.0,
+            }),
+            next_sequence_number: AtomicU16::new(0),
+            target_bitrate_bps: AtomicU64::new(
+                INITIAL_BITRATE_BPS.clamp(min_bitrate_bps, max_bitrate_bps),
+            ),
+            congested: AtomicBool::new(false),
+            min_bitrate_bps,
+            max_bitrate_bps,
+        }
+    }
+
+    /// Reads `MIN_BITRATE_BPS`/`MAX_BITRATE_BPS` (via
+    /// `crate::config::min_bitrate_bps`/`max_bitrate_bps`) and builds a
+    /// controller bounded by them.
+    pub fn from_env() -> Self {
+        Self::new(
+            crate::config::min_bitrate_bps(),
+            crate::config::max_bitrate_bps(),
+        )
+    }
+
+    /// Allocates the next transport-wide sequence number for a packet about
+    /// to be stamped and forwarded (see
+    /// `crate::broadcaster::rewrite_and_buffer`).
+    pub fn next_sequence_number(&self) -> u16 {
+        self.next_sequence_number.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Records that the packet tagged with transport-wide sequence number
+    /// `seq` was just sent, so a later `TransportLayerCc` report naming it
+    /// can be turned into a delay sample.
+    pub async fn record_sent(&self, seq: u16) {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+        state.sent.insert(seq, now);
+        if state.sent.len() > SENT_MAP_PRUNE_THRESHOLD {
+            state
+                .sent
+                .retain(|_, sent_at| now.duration_since(*sent_at) < SENT_ENTRY_TTL);
+        }
+    }
+
+    /// Folds a `TransportLayerCc` feedback report and the subscriber's
+    /// latest reported loss percentage into the over-use detector and
+    /// steps `target_bitrate_bps` accordingly.
+    pub async fn on_feedback(&self, feedback: &TransportLayerCc, loss_pct: f32) {
+        let mut over_use = false;
+        let mut samples = 0u32;
+
+        {
+            let mut state = self.state.lock().await;
+            let statuses =
+                expand_packet_statuses(&feedback.packet_chunks, feedback.packet_status_count);
+            let mut recv_deltas = feedback.recv_deltas.iter();
+            let mut seq = feedback.base_sequence_number;
+
+            for status in statuses {
+                let this_seq = seq;
+                seq = seq.wrapping_add(1);
+
+                if status == SymbolTypeTcc::PacketNotReceived {
+                    continue;
+                }
+                // Only statuses other than `PacketNotReceived` consume a
+                // `recv_deltas` entry - that's what made the old "one delta
+                // per sequence number" assumption wrong as soon as a report
+                // covered any loss.
+                let Some(recv_delta) = recv_deltas.next() else {
+                    break;
+                };
+
+                let Some(sent_at) = state.sent.remove(&this_seq) else {
+                    continue;
+                };
+
+                // `delta` is the library's tick-encoded inter-arrival gap
+                // (250us ticks per the transport-wide-cc draft's "small
+                // delta" symbol); this is the only unit conversion this
+                // controller assumes about the feedback wire format.
+                let arrival_delta_ms = recv_delta.delta as f64 * 0.25;
+                let departure_delta_ms = match state.last_departure {
+                    Some(last) => sent_at.saturating_duration_since(last).as_secs_f64() * 1000.0,
+                    None => arrival_delta_ms,
+                };
+                state.last_departure = Some(sent_at);
+
+                let gradient = arrival_delta_ms - departure_delta_ms;
+                state.smoothed_gradient_ms +=
+                    (gradient - state.smoothed_gradient_ms) * GRADIENT_SMOOTHING;
+                samples += 1;
+                if state.smoothed_gradient_ms > OVERUSE_THRESHOLD_MS {
+                    over_use = true;
+                }
+            }
+        }
+
+        if samples == 0 {
+            return;
+        }
+
+        let decrease = over_use || loss_pct > LOSS_DECREASE_THRESHOLD_PCT;
+        self.step_bitrate(decrease);
+    }
+
+    fn step_bitrate(&self, decrease: bool) {
+        let current = self.target_bitrate_bps.load(Ordering::Relaxed);
+        let next = if decrease {
+            ((current as f64) * DECREASE_FACTOR) as u64
+        } else {
+            current.saturating_add(INCREASE_STEP_BPS)
+        };
+        self.target_bitrate_bps.store(
+            next.clamp(self.min_bitrate_bps, self.max_bitrate_bps),
+            Ordering::Relaxed,
+        );
+        self.congested.store(decrease, Ordering::Relaxed);
+    }
+
+    /// Current target bitrate in bits per second.
+    pub fn target_bitrate_bps(&self) -> u64 {
+        self.target_bitrate_bps.load(Ordering::Relaxed)
+    }
+
+    /// Whether the subscriber's last feedback report showed over-use or
+    /// high loss, consulted by `crate::broadcaster`'s writer consumer loop
+    /// to decide whether to drop a non-keyframe frame rather than forward
+    /// it.
+    pub fn is_congested(&self) -> bool {
+        self.congested.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,296 @@
+//! Pluggable event connector: streams SFU session/track/connection lifecycle
+//! events to an external sink (SQL store, Kafka, HTTP, ...) for durable
+//! audit/analytics beyond the in-memory counters in `crate::metrics`.
+//!
+//! Emitting an event never blocks signaling: `ConnectorHandle::emit` hands
+//! the event to a bounded channel via `try_send`, so a slow or unreachable
+//! sink drops events (counted in `SFU_PACKETS_DROPPED_TOTAL` under the
+//! `connector_backpressure` reason, the same metric the broadcast path
+//! already uses for its own backpressure) instead of stalling
+//! `create_session`/`delete_session`/the track-forwarding loop. Disabled
+//! unless `SFU_CONNECTOR_DATABASE_URL` is set, mirroring
+//! `crate::captions::CaptioningConfig`'s opt-in-via-env shape.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+use crate::metrics::SFU_PACKETS_DROPPED_TOTAL;
+
+/// Depth of the bounded channel between event producers (`create_session`,
+/// `delete_session`, `track_handler`) and the batching drain task.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// How many events to accumulate per sink write, mirroring
+/// `broadcaster::CoalesceConfig`'s bytes-or-deadline batching shape.
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Flush whatever's buffered if this much time passes without reaching
+/// `DEFAULT_BATCH_SIZE`, so a quiet room's events aren't held back
+/// indefinitely waiting to fill a batch.
+const BATCH_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What kind of lifecycle moment a `ConnectorEvent` records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectorEventType {
+    SessionCreated,
+    SessionDeleted,
+    TrackAdded,
+    TrackRemoved,
+    ConnectionEstablished,
+    ConnectionFailed,
+    KeyframeRequested,
+}
+
+impl ConnectorEventType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::SessionCreated => "session_created",
+            Self::SessionDeleted => "session_deleted",
+            Self::TrackAdded => "track_added",
+            Self::TrackRemoved => "track_removed",
+            Self::ConnectionEstablished => "connection_established",
+            Self::ConnectionFailed => "connection_failed",
+            Self::KeyframeRequested => "keyframe_requested",
+        }
+    }
+}
+
+/// One structured lifecycle event, handed off to the background drain task
+/// for batched delivery to an `EventSink`.
+#[derive(Debug, Clone)]
+pub struct ConnectorEvent {
+    pub room_id: String,
+    pub user_id: String,
+    pub stream_id: Option<String>,
+    pub track_kind: Option<String>,
+    pub event_type: ConnectorEventType,
+    /// Unix epoch milliseconds.
+    pub ts: u64,
+}
+
+impl ConnectorEvent {
+    pub fn new(
+        event_type: ConnectorEventType,
+        room_id: impl Into<String>,
+        user_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            room_id: room_id.into(),
+            user_id: user_id.into(),
+            stream_id: None,
+            track_kind: None,
+            event_type,
+            ts: now_unix_millis(),
+        }
+    }
+
+    /// Attaches the stream/track-kind this event is about, for the
+    /// `TrackAdded`/`TrackRemoved`/`KeyframeRequested` variants.
+    pub fn with_track(mut self, stream_id: impl Into<String>, track_kind: impl Into<String>) -> Self {
+        self.stream_id = Some(stream_id.into());
+        self.track_kind = Some(track_kind.into());
+        self
+    }
+}
+
+fn now_unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[derive(Debug)]
+pub struct SinkError(pub String);
+
+impl std::fmt::Display for SinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+/// A pluggable destination for batches of `ConnectorEvent`s. Implement this
+/// to swap the default SQL-backed store for Kafka, an HTTP webhook, etc.
+#[tonic::async_trait]
+pub trait EventSink: Send + Sync {
+    async fn write(&self, events: &[ConnectorEvent]) -> Result<(), SinkError>;
+}
+
+/// SQL-backed `EventSink`, storing events in an `events` table indexed on
+/// `room_id` and `ts` for durable audit/analytics queries. Works against any
+/// `sqlx`-supported backend (Postgres, MySQL, SQLite) via `AnyPool`.
+pub struct SqlEventSink {
+    pool: sqlx::AnyPool,
+}
+
+impl SqlEventSink {
+    /// Connects to `database_url` and ensures the `events` table (and its
+    /// indexes) exist.
+    pub async fn connect(database_url: &str) -> Result<Self, SinkError> {
+        let pool = sqlx::AnyPool::connect(database_url)
+            .await
+            .map_err(|e| SinkError(e.to_string()))?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS events (
+                id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                room_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                stream_id TEXT,
+                track_kind TEXT,
+                event_type TEXT NOT NULL,
+                ts BIGINT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| SinkError(e.to_string()))?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS events_room_id_idx ON events (room_id)")
+            .execute(&pool)
+            .await
+            .map_err(|e| SinkError(e.to_string()))?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS events_ts_idx ON events (ts)")
+            .execute(&pool)
+            .await
+            .map_err(|e| SinkError(e.to_string()))?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[tonic::async_trait]
+impl EventSink for SqlEventSink {
+    async fn write(&self, events: &[ConnectorEvent]) -> Result<(), SinkError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| SinkError(e.to_string()))?;
+
+        for event in events {
+            sqlx::query(
+                "INSERT INTO events (room_id, user_id, stream_id, track_kind, event_type, ts)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&event.room_id)
+            .bind(&event.user_id)
+            .bind(&event.stream_id)
+            .bind(&event.track_kind)
+            .bind(event.event_type.as_str())
+            .bind(event.ts as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| SinkError(e.to_string()))?;
+        }
+
+        tx.commit().await.map_err(|e| SinkError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Handle producers use to emit events without ever blocking on the sink.
+#[derive(Clone)]
+pub struct ConnectorHandle {
+    tx: mpsc::Sender<ConnectorEvent>,
+}
+
+impl ConnectorHandle {
+    /// Reads `SFU_CONNECTOR_DATABASE_URL` and, if set, connects a
+    /// `SqlEventSink` and spawns its drain task. Returns `None` (the
+    /// default) when unset, since most deployments don't run an events
+    /// store.
+    pub async fn from_env() -> Option<Self> {
+        let database_url = std::env::var("SFU_CONNECTOR_DATABASE_URL").ok()?;
+        match SqlEventSink::connect(&database_url).await {
+            Ok(sink) => Some(spawn_connector(Arc::new(sink))),
+            Err(e) => {
+                error!(error = %e, "[Connector] Failed to connect event sink; events will not be recorded");
+                None
+            }
+        }
+    }
+
+    /// Hands `event` to the background drain task. Never blocks: if the
+    /// channel is full (the sink can't keep up) or the drain task has
+    /// exited, the event is dropped and counted under
+    /// `SFU_PACKETS_DROPPED_TOTAL{reason="connector_backpressure"}` rather
+    /// than stalling the caller's signaling path.
+    pub fn emit(&self, event: ConnectorEvent) {
+        if self.tx.try_send(event).is_err() {
+            SFU_PACKETS_DROPPED_TOTAL
+                .with_label_values(&["connector_backpressure"])
+                .inc();
+        }
+    }
+}
+
+/// Spawns the background task that batches events off the channel and
+/// writes them to `sink`, returning the handle producers emit through.
+///
+/// Batches flush once `DEFAULT_BATCH_SIZE` events have accumulated or
+/// `BATCH_FLUSH_INTERVAL` has passed, whichever comes first. A batch that
+/// fails to write is retried once immediately; a second failure drops it
+/// (counted the same way as channel backpressure) rather than buffering
+/// unboundedly against a sink that may be down for a while.
+pub fn spawn_connector(sink: Arc<dyn EventSink>) -> ConnectorHandle {
+    let (tx, mut rx) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        let mut batch = Vec::with_capacity(DEFAULT_BATCH_SIZE);
+        let mut ticker = tokio::time::interval(BATCH_FLUSH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                event = rx.recv() => {
+                    match event {
+                        Some(event) => {
+                            batch.push(event);
+                            if batch.len() >= DEFAULT_BATCH_SIZE {
+                                flush(&sink, &mut batch).await;
+                            }
+                        }
+                        None => {
+                            flush(&sink, &mut batch).await;
+                            break;
+                        }
+                    }
+                }
+                _ = ticker.tick() => {
+                    flush(&sink, &mut batch).await;
+                }
+            }
+        }
+
+        debug!("[Connector] Drain task exiting: channel closed");
+    });
+
+    ConnectorHandle { tx }
+}
+
+async fn flush(sink: &Arc<dyn EventSink>, batch: &mut Vec<ConnectorEvent>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    if let Err(e) = sink.write(batch).await {
+        warn!(error = %e, "[Connector] Sink write failed, retrying once");
+        if let Err(e) = sink.write(batch).await {
+            error!(
+                error = %e,
+                dropped = batch.len(),
+                "[Connector] Sink write failed twice, dropping batch"
+            );
+            SFU_PACKETS_DROPPED_TOTAL
+                .with_label_values(&["connector_backpressure"])
+                .inc_by(batch.len() as u64);
+        }
+    }
+
+    batch.clear();
+}
@@ -0,0 +1,131 @@
+//! Reliable/lossy SCTP data channels alongside the gRPC event stream.
+//!
+//! Every session gets two `RTCDataChannel`s set up before its first offer is
+//! created (so the SCTP association is negotiated in that same SDP): a
+//! default ordered/reliable channel for chat, admin actions, and room-state
+//! sync, and an unordered, zero-retransmit "lossy" channel for ephemeral
+//! signals like hand-raise flicker or cursor/reaction events, where a late
+//! duplicate is worse than a dropped one head-of-line blocking the rest of
+//! the channel.
+//!
+//! Both carry the same `WebSocketMessage` envelope already defined in
+//! `crate::pb::signaling` for the WebSocket transport — clients pick a
+//! channel per message class instead of this crate inventing a second wire
+//! format. This module only establishes the transport and the
+//! `DataChannelReady` handshake; inbound messages are decoded and logged,
+//! since reacting to each message kind (persisting chat, applying admin
+//! actions, ...) is the same downstream logic the WebSocket path already
+//! owns elsewhere, not something this crate implements today.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use prost::Message;
+use tracing::{debug, info, warn};
+use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+use webrtc::data_channel::data_channel_message::DataChannelMessage;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::peer_connection::RTCPeerConnection;
+
+use crate::pb;
+use crate::pb::sfu::sfu_event::Payload as EventPayload;
+use crate::types::SharedEventSender;
+
+const RELIABLE_LABEL: &str = "reliable";
+const LOSSY_LABEL: &str = "lossy";
+
+/// Creates this peer's reliable and lossy data channels and wires them to
+/// emit `DataChannelReady` once both have opened. Must be called before the
+/// peer's first offer is generated — `create_data_channel` only ends up in
+/// the SDP if it runs before `create_offer`.
+pub async fn setup_data_channels(
+    pc: &Arc<RTCPeerConnection>,
+    event_tx: &SharedEventSender,
+    user_id: &str,
+    can_publish_data: bool,
+) {
+    let reliable = match pc.create_data_channel(RELIABLE_LABEL, None).await {
+        Ok(dc) => dc,
+        Err(e) => {
+            warn!(%user_id, error = %e, "[SFU] Failed to create reliable data channel");
+            return;
+        }
+    };
+
+    let lossy_init = RTCDataChannelInit {
+        ordered: Some(false),
+        max_retransmits: Some(0),
+        ..Default::default()
+    };
+    let lossy = match pc.create_data_channel(LOSSY_LABEL, Some(lossy_init)).await {
+        Ok(dc) => dc,
+        Err(e) => {
+            warn!(%user_id, error = %e, "[SFU] Failed to create lossy data channel");
+            return;
+        }
+    };
+
+    // Flips to `true` the first time either channel opens; the second one
+    // to fire sees it already set and knows it's the one that should emit
+    // the handshake event, instead of both (or neither) emitting it.
+    let other_opened = Arc::new(AtomicBool::new(false));
+    register_open_handler(&reliable, other_opened.clone(), event_tx.clone(), user_id.to_string());
+    register_open_handler(&lossy, other_opened, event_tx.clone(), user_id.to_string());
+
+    register_message_handler(&reliable, user_id.to_string(), can_publish_data);
+    register_message_handler(&lossy, user_id.to_string(), can_publish_data);
+}
+
+fn register_open_handler(
+    dc: &Arc<RTCDataChannel>,
+    other_opened: Arc<AtomicBool>,
+    event_tx: SharedEventSender,
+    user_id: String,
+) {
+    let label = dc.label().to_string();
+    dc.on_open(Box::new(move || {
+        let other_opened = other_opened.clone();
+        let event_tx = event_tx.clone();
+        let user_id = user_id.clone();
+        Box::pin(async move {
+            debug!(%user_id, channel = %label, "[SFU] Data channel opened");
+            if other_opened.swap(true, Ordering::SeqCst) {
+                info!(%user_id, "[SFU] Both data channels open, sending DataChannelReady");
+                event_tx
+                    .emit(EventPayload::DataChannelReady(
+                        pb::signaling::DataChannelReadyEvent {
+                            reliable: true,
+                            lossy: true,
+                        },
+                    ))
+                    .await;
+            }
+        })
+    }));
+}
+
+/// Decodes inbound bytes as a `WebSocketMessage` envelope. Nothing in this
+/// crate consumes the per-kind payloads yet (see the module doc comment);
+/// this is the hook a future change adds per-kind dispatch to. Messages from
+/// a peer whose grant has `can_publish_data: false` are dropped undecoded.
+fn register_message_handler(dc: &Arc<RTCDataChannel>, user_id: String, can_publish_data: bool) {
+    let label = dc.label().to_string();
+    dc.on_message(Box::new(move |msg: DataChannelMessage| {
+        let user_id = user_id.clone();
+        let label = label.clone();
+        Box::pin(async move {
+            if !can_publish_data {
+                debug!(%user_id, channel = %label, "[SFU] Dropping data channel message; grant forbids publishing data");
+                return;
+            }
+
+            match pb::signaling::WebSocketMessage::decode(msg.data) {
+                Ok(_envelope) => {
+                    debug!(%user_id, channel = %label, "[SFU] Data channel message decoded");
+                }
+                Err(e) => {
+                    warn!(%user_id, channel = %label, error = %e, "[SFU] Failed to decode data channel message");
+                }
+            }
+        })
+    }));
+}
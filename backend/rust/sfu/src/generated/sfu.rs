@@ -5,6 +5,16 @@ pub struct CreateSessionRequest {
     pub room_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub user_id: ::prost::alloc::string::String,
+    /// Signed room access token (see `crate::auth`). Empty is only accepted
+    /// when the SFU has no `SFU_JWT_SECRET` configured.
+    #[prost(string, tag = "3")]
+    pub access_token: ::prost::alloc::string::String,
+    /// Lease duration for this session: if it isn't refreshed by a
+    /// `KeepAlive` ping within this many seconds of being created (or of the
+    /// last refresh), the reaper tears it down as if the client had crashed.
+    /// `0` preserves the old behavior of never expiring on its own.
+    #[prost(uint64, tag = "4")]
+    pub ttl_seconds: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -12,6 +22,12 @@ pub struct CreateSessionResponse {
     /// The SFU always initiates the connection
     #[prost(string, tag = "1")]
     pub sdp_offer: ::prost::alloc::string::String,
+    /// Server-generated id for this connection attempt, distinguishing it
+    /// from any prior/concurrent session for the same `(room_id, user_id)`.
+    /// Echo this back in `ListenRequest`/`SignalMessage`/`DeleteSessionRequest`
+    /// to address this specific session.
+    #[prost(string, tag = "2")]
+    pub session_id: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -20,8 +36,14 @@ pub struct SignalMessage {
     pub room_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub user_id: ::prost::alloc::string::String,
-    #[prost(oneof = "signal_message::Payload", tags = "3, 4, 5")]
+    #[prost(oneof = "signal_message::Payload", tags = "3, 4, 5, 6, 7, 8")]
     pub payload: ::core::option::Option<signal_message::Payload>,
+    /// The session this signal applies to, as returned by
+    /// `CreateSessionResponse::session_id`. Empty resolves to this user's
+    /// sole/most-recent session in the room, for older clients that haven't
+    /// adopted session ids yet.
+    #[prost(string, tag = "9")]
+    pub session_id: ::prost::alloc::string::String,
 }
 /// Nested message and enum types in `SignalMessage`.
 pub mod signal_message {
@@ -34,8 +56,45 @@ pub mod signal_message {
         IceCandidate(::prost::alloc::string::String),
         #[prost(string, tag = "5")]
         SdpOffer(::prost::alloc::string::String),
+        /// Switches the sending subscriber onto a different simulcast layer
+        /// for one of its subscriptions.
+        #[prost(message, tag = "6")]
+        SelectLayer(super::SelectLayerRequest),
+        /// Mutes/unmutes one of the caller's own published tracks in place.
+        #[prost(message, tag = "7")]
+        SetTrackEnabled(super::SetTrackEnabledRequest),
+        /// Updates the caller's own roster metadata (mute/deaf/speaking/name).
+        #[prost(message, tag = "8")]
+        UpdateParticipant(super::ParticipantUpdate),
     }
 }
+/// Asks the SFU to forward a different simulcast encoding of
+/// `target_user_id`'s `stream_id` to the caller, identified by RID (e.g.
+/// `"f"`/`"h"`/`"q"` for full/half/quarter resolution).
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SelectLayerRequest {
+    #[prost(string, tag = "1")]
+    pub target_user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stream_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub rid: ::prost::alloc::string::String,
+}
+/// Mutes/unmutes the caller's own `stream_id`/`track_kind` track. The SFU
+/// stops forwarding packets for it without tearing down or re-adding the
+/// underlying `RTCRtpTransceiver`, so no renegotiation is needed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetTrackEnabledRequest {
+    #[prost(string, tag = "1")]
+    pub stream_id: ::prost::alloc::string::String,
+    /// "video" or "audio"
+    #[prost(string, tag = "2")]
+    pub track_kind: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub enabled: bool,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SignalResponse {
@@ -49,6 +108,12 @@ pub struct DeleteSessionRequest {
     pub room_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub user_id: ::prost::alloc::string::String,
+    /// The session to tear down, as returned by
+    /// `CreateSessionResponse::session_id`. Empty tears down every session
+    /// this user currently holds in the room, matching the pre-session-id
+    /// behavior.
+    #[prost(string, tag = "3")]
+    pub session_id: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -58,17 +123,301 @@ pub struct DeleteSessionResponse {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeepAliveRequest {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub user_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeepAliveResponse {
+    /// Seconds remaining on the lease as of this ping, echoing the session's
+    /// `ttl_seconds` so the client can pace its next ping instead of
+    /// guessing.
+    #[prost(uint64, tag = "1")]
+    pub ttl_seconds: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListenRequest {
     #[prost(string, tag = "1")]
     pub room_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub user_id: ::prost::alloc::string::String,
+    /// Resumes a dropped stream at the first buffered event after this
+    /// sequence number. Unset (or a sequence older than the server's
+    /// buffered window) replays from the start of what's retained; a gap
+    /// older than that fails the call with `data_loss` so the client knows
+    /// to do a full resync instead of silently missing events.
+    #[prost(uint64, optional, tag = "3")]
+    pub resume_from_sequence: ::core::option::Option<u64>,
+    /// The session to listen on, as returned by
+    /// `CreateSessionResponse::session_id`. Empty resolves to this user's
+    /// sole/most-recent session in the room, for older clients that haven't
+    /// adopted session ids yet.
+    #[prost(string, tag = "4")]
+    pub session_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerLeftEvent {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+}
+/// Sent to every live peer right before `MySfu::shutdown` closes its
+/// `RTCPeerConnection`, so a well-behaved client can distinguish a graceful
+/// drain from a network failure and reconnect proactively instead of
+/// waiting out its own timeout.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ServerShutdownEvent {
+    #[prost(string, tag = "1")]
+    pub reason: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackInboundStats {
+    #[prost(uint64, tag = "1")]
+    pub packets_received: u64,
+    #[prost(uint64, tag = "2")]
+    pub bytes_received: u64,
+    #[prost(uint64, tag = "3")]
+    pub packets_lost: u64,
+    /// RFC 3550 §6.4.1 interarrival jitter estimate, in RTP timestamp units.
+    #[prost(uint32, tag = "4")]
+    pub jitter: u32,
+    /// Unix epoch millis of the last received keyframe, or 0 if none yet.
+    #[prost(int64, tag = "5")]
+    pub last_keyframe_ts_ms: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackOutboundStats {
+    /// The subscriber this forwarding leg serves.
+    #[prost(string, tag = "1")]
+    pub subscriber_user_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub packets_forwarded: u64,
+    #[prost(uint64, tag = "3")]
+    pub bytes_forwarded: u64,
+    #[prost(uint64, tag = "4")]
+    pub nack_count: u64,
+    #[prost(uint64, tag = "5")]
+    pub pli_count: u64,
+    #[prost(uint64, tag = "6")]
+    pub packets_lost: u64,
+    #[prost(uint32, tag = "7")]
+    pub jitter: u32,
+    #[prost(uint32, tag = "8")]
+    pub round_trip_time_ms: u32,
+    /// Writes to this writer that returned an error (e.g. a disconnected
+    /// subscriber), accumulated since the writer was added.
+    #[prost(uint64, tag = "9")]
+    pub send_failures: u64,
+    /// Unix epoch millis of the last successful forward, or 0 if none yet.
+    #[prost(int64, tag = "10")]
+    pub last_success_ms: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackStats {
+    #[prost(string, tag = "1")]
+    pub stream_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub track_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub inbound: ::core::option::Option<TrackInboundStats>,
+    #[prost(message, repeated, tag = "4")]
+    pub outbound: ::prost::alloc::vec::Vec<TrackOutboundStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStatsRequest {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub user_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetStatsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub tracks: ::prost::alloc::vec::Vec<TrackStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatsSnapshotEvent {
+    #[prost(message, repeated, tag = "1")]
+    pub tracks: ::prost::alloc::vec::Vec<TrackStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConnectionQualityEvent {
+    /// The publisher whose track this score describes.
+    #[prost(string, tag = "1")]
+    pub target_user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stream_id: ::prost::alloc::string::String,
+    /// 1 (poor) through 4 (excellent).
+    #[prost(uint32, tag = "3")]
+    pub score: u32,
+}
+/// Periodic 1-5 score for a peer's own uplink into the SFU, computed from
+/// `pc.get_stats()` RTCP loss/RTT and exponentially smoothed to avoid
+/// flapping (see `crate::sfu_service::spawn_session_stats_collector`).
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionQualityEvent {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    /// 1 (poor) through 5 (excellent). Unset until the peer has sent enough
+    /// RTP to sample loss/RTT from.
+    #[prost(uint32, optional, tag = "2")]
+    pub score: ::core::option::Option<u32>,
+    #[prost(uint32, tag = "3")]
+    pub rtt_ms: u32,
+    #[prost(float, tag = "4")]
+    pub loss_pct: f32,
+}
+/// RFC 7273 clock alignment for one publisher's stream, derived from the
+/// NTP/RTP timestamp pair in its latest inbound RTCP Sender Report.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClockSyncEvent {
+    #[prost(string, tag = "1")]
+    pub stream_id: ::prost::alloc::string::String,
+    /// Same value as this session's `a=ts-refclk` attribute, e.g.
+    /// `ntp=/traceable/` or `ptp=IEEE1588-2008:<grandmaster>:<domain>`.
+    #[prost(string, tag = "2")]
+    pub refclk: ::prost::alloc::string::String,
+    /// The RTP timestamp that corresponds to the Sender Report's NTP time,
+    /// i.e. this stream's timestamp base on the shared reference clock.
+    #[prost(uint32, tag = "3")]
+    pub rtp_offset: u32,
+}
+/// A room's live roster entry. Sent in full on `ParticipantJoined`; later
+/// changes arrive as incremental `ParticipantUpdate`s instead of another
+/// full copy.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParticipantInfo {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub display_name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub muted: bool,
+    #[prost(bool, tag = "4")]
+    pub deafened: bool,
+    #[prost(bool, tag = "5")]
+    pub speaking: bool,
+}
+/// Carries only the fields that changed for `user_id`; unset fields leave
+/// the stored `ParticipantInfo` field as-is.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ParticipantUpdate {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub display_name: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(bool, optional, tag = "3")]
+    pub muted: ::core::option::Option<bool>,
+    #[prost(bool, optional, tag = "4")]
+    pub deafened: ::core::option::Option<bool>,
+    #[prost(bool, optional, tag = "5")]
+    pub speaking: ::core::option::Option<bool>,
+}
+/// Presence notification for a room's participant registry.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoomUpdateEvent {
+    #[prost(oneof = "room_update_event::Update", tags = "1, 2, 3")]
+    pub update: ::core::option::Option<room_update_event::Update>,
+}
+/// Nested message and enum types in `RoomUpdateEvent`.
+pub mod room_update_event {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Update {
+        #[prost(message, tag = "1")]
+        ParticipantJoined(super::ParticipantInfo),
+        #[prost(string, tag = "2")]
+        ParticipantLeft(::prost::alloc::string::String),
+        #[prost(message, tag = "3")]
+        ParticipantUpdated(super::ParticipantUpdate),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSessionStatsRequest {
+    #[prost(string, tag = "1")]
+    pub room_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub user_id: ::prost::alloc::string::String,
+}
+/// A snapshot of the session's `RTCPeerConnection` stats, sourced from
+/// `pc.get_stats()` rather than the RTCP bookkeeping behind `TrackStats`.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionStatsSnapshot {
+    #[prost(uint64, tag = "1")]
+    pub inbound_bitrate_bps: u64,
+    #[prost(uint64, tag = "2")]
+    pub outbound_bitrate_bps: u64,
+    #[prost(uint64, tag = "3")]
+    pub packets_lost: u64,
+    #[prost(uint32, tag = "4")]
+    pub jitter_ms: u32,
+    #[prost(uint32, tag = "5")]
+    pub round_trip_time_ms: u32,
+    /// Number of tracks this session currently has forwarded, in either
+    /// direction.
+    #[prost(uint32, tag = "6")]
+    pub forwarded_track_count: u32,
+}
+/// Sent when a `BroadcasterWriter` is dropped from `TrackBroadcaster::writers`
+/// — either the publisher stopped the track or the subscriber unsubscribed —
+/// so the receiving peer can release its decoder deterministically instead
+/// of waiting out an RTP timeout.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackRemovedEvent {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stream_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub ssrc: u32,
+}
+/// Mirrors `TrackRemovedEvent`'s PUT/DELETE-style granularity for in-place
+/// mute toggles: keyed by the forwarded SSRC rather than `(user_id,
+/// stream_id, track_kind)`, so a subscriber can flip exactly the encoding
+/// it's receiving without re-deriving which track that SSRC belongs to.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackStateChangedEvent {
+    #[prost(uint32, tag = "1")]
+    pub ssrc: u32,
+    #[prost(bool, tag = "2")]
+    pub muted: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SfuEvent {
-    #[prost(oneof = "sfu_event::Payload", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(
+        oneof = "sfu_event::Payload",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 14, 15, 16, 17, 18, 19"
+    )]
     pub payload: ::core::option::Option<sfu_event::Payload>,
+    /// Monotonically increasing per-(room, user) sequence number, assigned
+    /// by the SFU's `EventStream` when the event is emitted. Lets a client
+    /// that reconnects a `ListenEvents`/`Signal` stream pass back the last
+    /// sequence it saw in `ListenRequest::resume_from_sequence` to resume
+    /// without replaying or dropping events.
+    #[prost(uint64, tag = "13")]
+    pub sequence: u64,
 }
 /// Nested message and enum types in `SfuEvent`.
 pub mod sfu_event {
@@ -89,6 +438,54 @@ pub mod sfu_event {
         SdpAnswer(::prost::alloc::string::String),
         #[prost(string, tag = "6")]
         IceCandidate(::prost::alloc::string::String),
+        /// Sent when a peer's connection is torn down, so other participants
+        /// in the room can drop its tiles/tracks.
+        #[prost(message, tag = "7")]
+        PeerLeft(super::PeerLeftEvent),
+        /// Periodic push of forwarding stats for this peer's published tracks.
+        #[prost(message, tag = "8")]
+        StatsSnapshot(super::StatsSnapshotEvent),
+        /// Sent when a subscriber's connection-quality bucket for a given
+        /// publisher's track changes (after hysteresis settles).
+        #[prost(message, tag = "9")]
+        ConnectionQuality(super::ConnectionQualityEvent),
+        /// Sent instead of `TrackEvent` when a publisher mutes/unmutes an
+        /// already-forwarded track in place.
+        #[prost(message, tag = "10")]
+        TrackMuted(super::super::signaling::TrackMutedEvent),
+        /// Sent once per publisher stream after its first inbound Sender
+        /// Report arrives, when the room has clock signaling enabled.
+        #[prost(message, tag = "11")]
+        ClockSync(super::ClockSyncEvent),
+        /// Sent whenever the room's participant registry changes: someone
+        /// joined, left, or updated their mute/deaf/speaking/name metadata.
+        #[prost(message, tag = "12")]
+        RoomUpdate(super::RoomUpdateEvent),
+        /// Sent when a `BroadcasterWriter` is dropped, so the subscriber can
+        /// release its decoder instead of waiting out an RTP timeout.
+        #[prost(message, tag = "14")]
+        TrackRemoved(super::TrackRemovedEvent),
+        /// Sent when a forwarded SSRC's mute state flips in place.
+        #[prost(message, tag = "15")]
+        TrackStateChanged(super::TrackStateChangedEvent),
+        /// A live transcription line for one publisher's audio track (see
+        /// `crate::captions`).
+        #[prost(message, tag = "16")]
+        Caption(super::super::signaling::CaptionEvent),
+        /// Sent once both the reliable and lossy `RTCDataChannel`s for this
+        /// peer have opened (see `crate::data_channels`).
+        #[prost(message, tag = "17")]
+        DataChannelReady(super::super::signaling::DataChannelReadyEvent),
+        /// Periodic 1-5 score for this peer's own uplink (not a specific
+        /// subscribed track — see `ConnectionQuality` for per-track scores),
+        /// derived from `pc.get_stats()` in
+        /// `crate::sfu_service::spawn_session_stats_collector`.
+        #[prost(message, tag = "18")]
+        SessionQuality(super::SessionQualityEvent),
+        /// Sent once to every peer as `MySfu::shutdown` begins draining the
+        /// server, right before its `RTCPeerConnection` is closed.
+        #[prost(message, tag = "19")]
+        ServerShutdown(super::ServerShutdownEvent),
     }
 }
 /// Generated server implementations.
@@ -112,6 +509,23 @@ pub mod sfu_service_server {
             &self,
             request: tonic::Request<super::SignalMessage>,
         ) -> std::result::Result<tonic::Response<super::SignalResponse>, tonic::Status>;
+        /// Bidirectional streaming response type for the Signal method.
+        type SignalStream: futures_core::Stream<
+                Item = std::result::Result<super::SfuEvent, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Long-lived replacement for `HandleSignal` + `ListenEvents`: the
+        /// client's answers/ICE/control messages flow up this same stream
+        /// that carries the SFU's renegotiation offers/track events back
+        /// down, keyed by the `room_id`/`user_id` in the opening message.
+        async fn signal(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::SignalMessage>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SignalStream>,
+            tonic::Status,
+        >;
         /// 3. Cleanup when a user leaves
         async fn delete_session(
             &self,
@@ -120,6 +534,29 @@ pub mod sfu_service_server {
             tonic::Response<super::DeleteSessionResponse>,
             tonic::Status,
         >;
+        /// Bidirectional streaming response type for the KeepAlive method.
+        type KeepAliveStream: futures_core::Stream<
+                Item = std::result::Result<super::KeepAliveResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Refreshes the lease on a session created with a non-zero
+        /// `ttl_seconds`: every inbound `KeepAliveRequest` pushes the
+        /// session's expiry back out, and the reaper tears it down (the same
+        /// way a lost connection would be) if it goes quiet for longer than
+        /// that.
+        async fn keep_alive(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::KeepAliveRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::KeepAliveStream>,
+            tonic::Status,
+        >;
+        /// 5. Snapshot of forwarding stats for a user's published tracks
+        async fn get_stats(
+            &self,
+            request: tonic::Request<super::GetStatsRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetStatsResponse>, tonic::Status>;
         /// Server streaming response type for the ListenEvents method.
         type ListenEventsStream: futures_core::Stream<
                 Item = std::result::Result<super::SfuEvent, tonic::Status>,
@@ -134,6 +571,20 @@ pub mod sfu_service_server {
             tonic::Response<Self::ListenEventsStream>,
             tonic::Status,
         >;
+        /// Server streaming response type for the GetSessionStats method.
+        type GetSessionStatsStream: futures_core::Stream<
+                Item = std::result::Result<super::SessionStatsSnapshot, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// 6. Periodic push of `RTCPeerConnection`-native stats for a session
+        async fn get_session_stats(
+            &self,
+            request: tonic::Request<super::GetSessionStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::GetSessionStatsStream>,
+            tonic::Status,
+        >;
     }
     /// SfuService defines the gRPC interface for the Rust Selective Forwarding Unit (SFU).
     /// It handles peer session management, signaling, and media routing in the Data Plane.
@@ -306,6 +757,53 @@ pub mod sfu_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/sfu.SfuService/Signal" => {
+                    #[allow(non_camel_case_types)]
+                    struct SignalSvc<T: SfuService>(pub Arc<T>);
+                    impl<
+                        T: SfuService,
+                    > tonic::server::StreamingService<super::SignalMessage>
+                    for SignalSvc<T> {
+                        type Response = super::SfuEvent;
+                        type ResponseStream = T::SignalStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::SignalMessage>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).signal(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SignalSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/sfu.SfuService/DeleteSession" => {
                     #[allow(non_camel_case_types)]
                     struct DeleteSessionSvc<T: SfuService>(pub Arc<T>);
@@ -352,6 +850,95 @@ pub mod sfu_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/sfu.SfuService/KeepAlive" => {
+                    #[allow(non_camel_case_types)]
+                    struct KeepAliveSvc<T: SfuService>(pub Arc<T>);
+                    impl<
+                        T: SfuService,
+                    > tonic::server::StreamingService<super::KeepAliveRequest>
+                    for KeepAliveSvc<T> {
+                        type Response = super::KeepAliveResponse;
+                        type ResponseStream = T::KeepAliveStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::KeepAliveRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).keep_alive(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = KeepAliveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/sfu.SfuService/GetStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetStatsSvc<T: SfuService>(pub Arc<T>);
+                    impl<T: SfuService> tonic::server::UnaryService<super::GetStatsRequest>
+                    for GetStatsSvc<T> {
+                        type Response = super::GetStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move { (*inner).get_stats(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/sfu.SfuService/ListenEvents" => {
                     #[allow(non_camel_case_types)]
                     struct ListenEventsSvc<T: SfuService>(pub Arc<T>);
@@ -399,6 +986,53 @@ pub mod sfu_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/sfu.SfuService/GetSessionStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSessionStatsSvc<T: SfuService>(pub Arc<T>);
+                    impl<
+                        T: SfuService,
+                    > tonic::server::ServerStreamingService<super::GetSessionStatsRequest>
+                    for GetSessionStatsSvc<T> {
+                        type Response = super::SessionStatsSnapshot;
+                        type ResponseStream = T::GetSessionStatsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetSessionStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).get_session_stats(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSessionStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
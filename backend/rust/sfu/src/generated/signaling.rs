@@ -4,7 +4,7 @@
 pub struct WebSocketMessage {
     #[prost(
         oneof = "web_socket_message::Payload",
-        tags = "1, 2, 3, 4, 5, 6, 7, 20, 21, 8, 9, 22, 23, 24, 25, 10, 11, 12, 13, 14, 15, 16, 17, 18, 26"
+        tags = "1, 2, 3, 4, 5, 6, 7, 20, 21, 8, 9, 22, 23, 24, 25, 10, 11, 12, 13, 14, 15, 16, 17, 18, 26, 27, 28"
     )]
     pub payload: ::core::option::Option<web_socket_message::Payload>,
 }
@@ -74,6 +74,12 @@ pub mod web_socket_message {
         /// --- Stream Mapping ---
         #[prost(message, tag = "26")]
         TrackAdded(super::TrackAddedEvent),
+        /// --- Live Captions ---
+        #[prost(message, tag = "27")]
+        Caption(super::CaptionEvent),
+        /// --- Data Channel Transport ---
+        #[prost(message, tag = "28")]
+        DataChannelReady(super::DataChannelReadyEvent),
     }
 }
 /// ---------------------------------------------------------
@@ -348,4 +354,58 @@ pub struct TrackAddedEvent {
     /// "video" or "audio"
     #[prost(string, tag = "3")]
     pub track_kind: ::prost::alloc::string::String,
+    /// Simulcast RIDs (see `crate::simulcast`) this stream currently has a
+    /// registered `TrackBroadcaster` for, e.g. `["f", "h", "q"]`; the order
+    /// of the entries is not defined. Empty for a non-simulcast track. Lets a subscriber
+    /// pick a starting layer via `SelectLayer` instead of always landing on
+    /// the default and discovering the others only after a downgrade.
+    #[prost(string, repeated, tag = "4")]
+    pub available_rids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Sent to subscribers when a publisher mutes/unmutes one of its tracks in
+/// place, instead of a `TrackAddedEvent`/renegotiation.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrackMutedEvent {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stream_id: ::prost::alloc::string::String,
+    /// "video" or "audio"
+    #[prost(string, tag = "3")]
+    pub track_kind: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub muted: bool,
+}
+/// ---------------------------------------------------------
+/// 9. Live Captions
+/// ---------------------------------------------------------
+/// A transcribed line of speech for one publisher, forwarded from
+/// `crate::captions` after the `cc.CaptioningService` returns it for an
+/// `AudioChunk` sampled off that publisher's audio `TrackBroadcaster`.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CaptionEvent {
+    #[prost(string, tag = "1")]
+    pub user_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub text: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub is_final: bool,
+    #[prost(float, tag = "4")]
+    pub confidence: f32,
+}
+/// ---------------------------------------------------------
+/// 10. Data Channel Transport
+/// ---------------------------------------------------------
+/// Sent once both SCTP data channels for a peer (see `crate::data_channels`)
+/// have finished opening, so clients know it's safe to start sending
+/// `WebSocketMessage`s over them instead of (or alongside) the WebSocket.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DataChannelReadyEvent {
+    #[prost(bool, tag = "1")]
+    pub reliable: bool,
+    #[prost(bool, tag = "2")]
+    pub lossy: bool,
 }
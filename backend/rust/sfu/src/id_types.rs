@@ -21,6 +21,21 @@ pub struct StreamId(pub Arc<String>);
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TrackId(pub Arc<String>);
 
+/// A strongly typed identifier for one `create_session` connection attempt,
+/// distinguishing a reconnect or a second concurrent tab for the same
+/// `(RoomId, UserId)` from the prior session instead of colliding with it in
+/// `PeerMap`. Generated server-side in `create_session` and echoed back in
+/// `CreateSessionResponse`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionId(pub Arc<String>);
+
+impl SessionId {
+    /// Mints a fresh, practically-unique session id.
+    pub fn generate() -> Self {
+        Self(Arc::new(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
 // Implement Display for easy logging
 impl fmt::Display for RoomId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -46,6 +61,12 @@ impl fmt::Display for TrackId {
     }
 }
 
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 // Implement conversion from String/&str
 impl From<String> for RoomId {
     fn from(s: String) -> Self {
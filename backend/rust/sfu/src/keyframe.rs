@@ -0,0 +1,159 @@
+//! Per-codec keyframe detection for the inbound forwarding loop in
+//! `track_handler`, so `mark_keyframe_received`/PLI-driven recovery work
+//! across codecs instead of only VP8/H264.
+
+/// Detects whether an RTP packet's payload starts (or belongs to) a
+/// keyframe for one codec.
+pub trait KeyframeDetector: Send + Sync {
+    fn is_keyframe(&self, payload: &[u8]) -> bool;
+}
+
+/// Picks a detector from the negotiated codec's `mime_type`
+/// (`track.codec().capability.mime_type`). Unknown mime types fall back to
+/// a detector that never reports a keyframe, matching prior behavior.
+pub fn detector_for_mime_type(mime_type: &str) -> Box<dyn KeyframeDetector> {
+    let mime_type = mime_type.to_lowercase();
+    if mime_type.contains("vp8") {
+        Box::new(Vp8Detector)
+    } else if mime_type.contains("vp9") {
+        Box::new(Vp9Detector)
+    } else if mime_type.contains("av1") {
+        Box::new(Av1Detector)
+    } else if mime_type.contains("h264") {
+        Box::new(H264Detector)
+    } else {
+        Box::new(UnknownDetector)
+    }
+}
+
+struct Vp8Detector;
+
+impl KeyframeDetector for Vp8Detector {
+    fn is_keyframe(&self, payload: &[u8]) -> bool {
+        // Key frame is bit 0 of the first payload byte == 0.
+        !payload.is_empty() && (payload[0] & 0x01) == 0
+    }
+}
+
+struct H264Detector;
+
+impl KeyframeDetector for H264Detector {
+    fn is_keyframe(&self, payload: &[u8]) -> bool {
+        let Some(&first) = payload.first() else {
+            return false;
+        };
+        let nal_type = first & 0x1F;
+        if nal_type == 5 {
+            true // IDR
+        } else if nal_type == 28 && payload.len() > 1 {
+            // FU-A: only the fragment starting the NAL unit carries type 5.
+            let s_bit = (payload[1] & 0x80) != 0;
+            let inner_type = payload[1] & 0x1F;
+            s_bit && inner_type == 5
+        } else {
+            false
+        }
+    }
+}
+
+struct Vp9Detector;
+
+impl KeyframeDetector for Vp9Detector {
+    fn is_keyframe(&self, payload: &[u8]) -> bool {
+        // VP9 payload descriptor, first byte: I P L F B E V Z.
+        let Some(&first) = payload.first() else {
+            return false;
+        };
+        let has_picture_id = first & 0x80 != 0;
+        let inter_picture_predicted = first & 0x40 != 0;
+        let has_layer_indices = first & 0x20 != 0;
+        let begins_frame = first & 0x08 != 0;
+
+        if inter_picture_predicted || !begins_frame {
+            return false;
+        }
+
+        let mut idx = 1;
+        if has_picture_id {
+            match payload.get(idx) {
+                Some(&b) if b & 0x80 != 0 => idx += 2, // 15-bit picture ID (M=1)
+                Some(_) => idx += 1,                    // 7-bit picture ID
+                None => return false,
+            }
+        }
+
+        if !has_layer_indices {
+            // No SVC layering: this is the only (spatial layer 0) stream.
+            return true;
+        }
+
+        // Layer indices byte: TID(3) U(1) SID(3) D(1). Only frames for
+        // spatial layer 0 establish a base-layer keyframe.
+        match payload.get(idx) {
+            Some(&b) => ((b >> 1) & 0x07) == 0,
+            None => false,
+        }
+    }
+}
+
+struct Av1Detector;
+
+impl KeyframeDetector for Av1Detector {
+    fn is_keyframe(&self, payload: &[u8]) -> bool {
+        // RTP AV1 aggregation header, first byte: Z Y W W N - - -.
+        let Some(&aggregation_header) = payload.first() else {
+            return false;
+        };
+        let new_coded_video_sequence = aggregation_header & 0x08 != 0;
+        if !new_coded_video_sequence {
+            return false;
+        }
+
+        // A sequence header OBU is required to open a new coded video
+        // sequence, and must be the first OBU in the aggregation unit when
+        // N is set. Where that OBU's header actually starts depends on W
+        // (bits 5-4): W == 1 means exactly one element whose size is
+        // implicit (it runs to the end of the payload), so the header sits
+        // right after the aggregation header. Any other W (0 = unknown
+        // count, or 2/3 = that many elements) means the first element is
+        // preceded by a LEB128-encoded size we have to skip first.
+        let num_obu_elements = (aggregation_header >> 4) & 0x03;
+        let obu_header_offset = if num_obu_elements == 1 {
+            1
+        } else {
+            match skip_leb128(payload, 1) {
+                Some(offset) => offset,
+                None => return false,
+            }
+        };
+
+        let Some(&obu_header) = payload.get(obu_header_offset) else {
+            return false;
+        };
+        let obu_type = (obu_header >> 3) & 0x0F;
+        const OBU_SEQUENCE_HEADER: u8 = 1;
+        obu_type == OBU_SEQUENCE_HEADER
+    }
+}
+
+/// Skips a LEB128-encoded unsigned integer starting at `payload[start]`,
+/// returning the index of the byte right after it (or `None` if the
+/// payload ends mid-encoding).
+fn skip_leb128(payload: &[u8], start: usize) -> Option<usize> {
+    let mut idx = start;
+    loop {
+        let &byte = payload.get(idx)?;
+        idx += 1;
+        if byte & 0x80 == 0 {
+            return Some(idx);
+        }
+    }
+}
+
+struct UnknownDetector;
+
+impl KeyframeDetector for UnknownDetector {
+    fn is_keyframe(&self, _payload: &[u8]) -> bool {
+        false
+    }
+}
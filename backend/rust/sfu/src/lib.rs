@@ -5,19 +5,45 @@ pub mod pb {
     pub mod sfu {
         include!("generated/sfu.rs");
     }
+    pub mod cc {
+        include!("generated/cc.rs");
+    }
 }
 
+pub mod auth;
+pub mod bandwidth;
 pub mod broadcaster;
+pub mod captions;
+pub mod config;
+pub mod congestion;
+pub mod connector;
+pub mod data_channels;
+pub mod id_types;
+pub mod keyframe;
+pub mod logging;
 pub mod media_setup;
+pub mod metrics;
 pub mod peer_manager;
+pub mod quality;
+pub mod room_manager;
 pub mod sfu_service;
 pub mod signaling_handler;
+pub mod simulcast;
+pub mod stats;
 pub mod track_handler;
 pub mod types;
+pub mod whip_whep;
 
 pub use media_setup::MediaSetup;
 pub use peer_manager::Peer;
 pub use types::{PeerMap, TrackMap};
 
+#[cfg(test)]
+use broadcaster::TrackBroadcaster;
+#[cfg(test)]
+use sfu_service::MySfu;
+
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod integration_tests;
@@ -4,7 +4,9 @@ use std::task::{Context, Poll};
 use tonic::{Request, Status};
 use tower::Service;
 use tower_layer::Layer;
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use uuid::Uuid;
 
 // Constants
 pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
@@ -32,6 +34,70 @@ pub fn get_correlation_id<T>(req: &Request<T>) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-// NOTE: Ideally we would implement a full tower middleware for this,
-// but for MVP/simplicity we can also just explicitly grab it in handlers
-// or basic interceptors. For now, this module provides the setup and helpers.
+/// `tower::Layer` that gives every gRPC request a correlation id.
+///
+/// It reads `x-correlation-id` off the incoming request (generating a fresh
+/// UUID when absent), enters a `tracing` span carrying that id for the whole
+/// request future, and writes the id back into the response metadata so the
+/// caller always knows which id was used. Because the JSON subscriber
+/// configured in [`init`] enables `with_current_span`/`with_span_list`, every
+/// structured log line emitted while the span is active — including deep
+/// inside `create_session`/`listen_events` — automatically carries the
+/// correlation id without handlers grabbing it manually.
+#[derive(Clone, Default)]
+pub struct CorrelationIdLayer;
+
+impl<S> Layer<S> for CorrelationIdLayer {
+    type Service = CorrelationIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CorrelationIdService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct CorrelationIdService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for CorrelationIdService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let correlation_id = req
+            .headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|val| val.to_str().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let span = tracing::info_span!("grpc_request", correlation_id = %correlation_id);
+
+        // Tower services must be ready before `call`; swap in a fresh clone
+        // so the in-flight call keeps the one `poll_ready` already primed.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let fut = async move {
+            let mut response = inner.call(req).await?;
+            if let Ok(value) = http::HeaderValue::from_str(&correlation_id) {
+                response
+                    .headers_mut()
+                    .insert(http::HeaderName::from_static(CORRELATION_ID_HEADER), value);
+            }
+            Ok(response)
+        };
+
+        Box::pin(fut.instrument(span))
+    }
+}
@@ -1,9 +1,13 @@
 use std::env;
+use std::sync::Arc;
 use tracing::error;
 use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::MediaEngine;
+use webrtc::api::setting_engine::SettingEngine;
 use webrtc::api::APIBuilder;
+use webrtc::ice::udp_mux::{UDPMux, UDPMuxDefault, UDPMuxParams};
 use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::ice_transport::ice_transport_policy::RTCIceTransportPolicy;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::policy::bundle_policy::RTCBundlePolicy;
@@ -11,10 +15,167 @@ use webrtc::rtp_transceiver::rtp_codec::{
     RTCRtpCodecCapability, RTCRtpHeaderExtensionCapability, RTPCodecType,
 };
 
+/// URI of the transport-wide congestion control header extension this
+/// media engine negotiates below, shared with `subscribe_to_existing_tracks`
+/// and `crate::track_handler` so they resolve the same negotiated id this
+/// engine registered rather than a hardcoded one.
+pub const TWCC_EXTENSION_URI: &str =
+    "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01";
+
+/// Binds a single UDP port and wraps it in a [`UDPMuxDefault`] that every
+/// `RTCPeerConnection` built from the returned API shares.
+///
+/// Demultiplexing is done by the ICE agent using the STUN USERNAME ufrag, so
+/// one bound socket is enough for an arbitrary number of peer connections
+/// instead of each one opening (and the firewall needing to allow) its own
+/// ephemeral port.
+pub async fn create_udp_mux(port: u16) -> std::io::Result<Arc<UDPMuxDefault>> {
+    let socket = tokio::net::UdpSocket::bind(("0.0.0.0", port)).await?;
+    Ok(UDPMuxDefault::new(UDPMuxParams::new(socket)))
+}
+
+/// ICE/TURN servers the SFU offers to peers during negotiation.
+///
+/// Operators configure this via environment variables so the same binary can
+/// be pointed at different STUN/TURN infrastructure per deployment.
+#[derive(Debug, Clone, Default)]
+pub struct IceServerConfig {
+    pub ice_servers: Vec<RTCIceServer>,
+    pub ice_transport_policy: RTCIceTransportPolicy,
+}
+
+impl IceServerConfig {
+    /// Builds the ICE server list from environment variables.
+    ///
+    /// `STUN_URL` (defaults to Google's public STUN server) is always included.
+    /// `ICE_SERVERS`, if set, is a comma-separated list of additional STUN/TURN
+    /// URLs (no credentials) appended alongside it — e.g. a pool of
+    /// geographically distinct STUN servers. `TURN_URL`/`TURN_USERNAME`/
+    /// `TURN_CREDENTIAL`, if all three are set, add one more TURN relay entry
+    /// carrying credentials. `ICE_TRANSPORT_POLICY` (`all`, the default, or
+    /// `relay`) controls whether peers may also gather host/srflx candidates
+    /// or must go through a TURN relay — set it to `relay` to force all media
+    /// through TURN in deployments that don't trust direct peer reachability.
+    pub fn from_env() -> Self {
+        let stun_url =
+            env::var("STUN_URL").unwrap_or_else(|_| "stun:stun.l.google.com:19302".to_string());
+
+        let mut ice_servers = vec![RTCIceServer {
+            urls: vec![stun_url],
+            ..Default::default()
+        }];
+
+        if let Ok(extra) = env::var("ICE_SERVERS") {
+            for url in extra.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                ice_servers.push(RTCIceServer {
+                    urls: vec![url.to_string()],
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let (Ok(turn_url), Ok(username), Ok(credential)) = (
+            env::var("TURN_URL"),
+            env::var("TURN_USERNAME"),
+            env::var("TURN_CREDENTIAL"),
+        ) {
+            ice_servers.push(RTCIceServer {
+                urls: vec![turn_url],
+                username,
+                credential,
+                ..Default::default()
+            });
+        }
+
+        let ice_transport_policy = match env::var("ICE_TRANSPORT_POLICY") {
+            Ok(val) if val.eq_ignore_ascii_case("relay") => RTCIceTransportPolicy::Relay,
+            _ => RTCIceTransportPolicy::All,
+        };
+
+        Self {
+            ice_servers,
+            ice_transport_policy,
+        }
+    }
+}
+
+/// Which reference clock (RFC 7273) the SFU advertises in `a=ts-refclk` so
+/// subscribers can align RTP timestamps from different streams/users to a
+/// common wall-clock origin for lip-sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockSignalingMode {
+    /// Don't advertise a reference clock; each stream stays best-effort.
+    Disabled,
+    /// Advertise the local system clock (`a=ts-refclk:ntp=/traceable/`).
+    #[default]
+    SystemClock,
+    /// Advertise a specific NTP server as the shared reference clock.
+    Ntp(std::net::IpAddr),
+    /// Advertise an IEEE 1588 (PTP) domain as the shared reference clock.
+    Ptp { grandmaster: String, domain: u8 },
+}
+
+impl ClockSignalingMode {
+    /// Reads `CLOCK_SIGNALING_MODE` (`disabled` | `system` | `ntp:<addr>` |
+    /// `ptp:<grandmaster>:<domain>`), defaulting to `system`.
+    pub fn from_env() -> Self {
+        match env::var("CLOCK_SIGNALING_MODE") {
+            Ok(val) if val.eq_ignore_ascii_case("disabled") => Self::Disabled,
+            Ok(val) if val.eq_ignore_ascii_case("system") => Self::SystemClock,
+            Ok(val) if val.to_lowercase().starts_with("ntp:") => val[4..]
+                .parse()
+                .map(Self::Ntp)
+                .unwrap_or(Self::SystemClock),
+            Ok(val) if val.to_lowercase().starts_with("ptp:") => {
+                let rest = &val[4..];
+                match rest.split_once(':') {
+                    Some((grandmaster, domain)) => Self::Ptp {
+                        grandmaster: grandmaster.to_string(),
+                        domain: domain.parse().unwrap_or(0),
+                    },
+                    None => Self::SystemClock,
+                }
+            }
+            _ => Self::SystemClock,
+        }
+    }
+
+    /// The reference-clock identity this mode advertises, in the same form
+    /// used as the value of `a=ts-refclk` — e.g. `ntp=/traceable/` or
+    /// `ptp=IEEE1588-2008:<grandmaster>:<domain>`. `None` when clock
+    /// signaling is disabled, so callers know not to emit `ClockSyncEvent`s.
+    pub fn refclk_label(self) -> Option<String> {
+        match self {
+            Self::Disabled => None,
+            Self::SystemClock => Some("ntp=/traceable/".to_string()),
+            Self::Ntp(addr) => Some(format!("ntp={}", addr)),
+            Self::Ptp { grandmaster, domain } => {
+                Some(format!("ptp=IEEE1588-2008:{}:{}", grandmaster, domain))
+            }
+        }
+    }
+
+    /// Renders the session-level `a=ts-refclk`/`a=mediaclk` attribute lines
+    /// for this mode, or an empty string when clock signalling is disabled.
+    fn sdp_lines(self) -> String {
+        match self {
+            Self::Disabled => String::new(),
+            // "traceable" means "this is the host's system clock, not
+            // synchronized to anything external" per RFC 7273 section 4.3.
+            Self::SystemClock => "a=ts-refclk:ntp=/traceable/\r\na=mediaclk:sender\r\n".to_string(),
+            Self::Ntp(addr) => format!("a=ts-refclk:ntp={}\r\na=mediaclk:direct=0\r\n", addr),
+            Self::Ptp { grandmaster, domain } => format!(
+                "a=ts-refclk:ptp=IEEE1588-2008:{}:{}\r\na=mediaclk:direct=0\r\n",
+                grandmaster, domain
+            ),
+        }
+    }
+}
+
 pub struct MediaSetup;
 
 impl MediaSetup {
-    pub fn create_webrtc_api() -> webrtc::api::API {
+    pub fn create_webrtc_api(udp_mux: Option<Arc<dyn UDPMux + Send + Sync>>) -> webrtc::api::API {
         let mut media_engine = MediaEngine::default();
 
         // Register Opus with FEC and low latency settings
@@ -79,16 +240,64 @@ impl MediaSetup {
                 error!("Failed to register H264 codec: {}", e);
             });
 
+        // RTX (RFC 4588): one retransmission payload type per primary video
+        // codec above, paired via `apt=` (associated payload type). See
+        // `crate::broadcaster::rtx_payload_type_for`/`wrap_as_rtx`, which
+        // mirror these pt pairings when answering a NACK.
+        media_engine
+            .register_codec(
+                RTCRtpCodecParameters {
+                    capability: RTCRtpCodecCapability {
+                        mime_type: "video/rtx".to_owned(),
+                        clock_rate: 90000,
+                        channels: 0,
+                        sdp_fmtp_line: "apt=96".to_owned(),
+                        ..Default::default()
+                    },
+                    payload_type: 97,
+                    ..Default::default()
+                },
+                RTPCodecType::Video,
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to register VP8 RTX codec: {}", e);
+            });
+
+        media_engine
+            .register_codec(
+                RTCRtpCodecParameters {
+                    capability: RTCRtpCodecCapability {
+                        mime_type: "video/rtx".to_owned(),
+                        clock_rate: 90000,
+                        channels: 0,
+                        sdp_fmtp_line: "apt=102".to_owned(),
+                        ..Default::default()
+                    },
+                    payload_type: 103,
+                    ..Default::default()
+                },
+                RTPCodecType::Video,
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to register H264 RTX codec: {}", e);
+            });
+
         let extensions = vec![
             "urn:ietf:params:rtp-hdrext:sdes:mid",
+            // RID and repaired-RID: required for webrtc-rs to demux a
+            // publisher's simulcast encodings into separate `on_track`
+            // callbacks (see `crate::simulcast`) instead of one mixed stream.
             "urn:ietf:params:rtp-hdrext:sdes:rtp-stream-id",
             "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id",
             "http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
-            "http://www.ietf.org/id/draft-holmer-rmcat-transport-wide-cc-extensions-01",
+            TWCC_EXTENSION_URI,
             "urn:ietf:params:rtp-hdrext:ssrc-audio-level",
             "urn:ietf:params:rtp-hdrext:toffset",
             "urn:3gpp:video-orientation",
             "http://www.webrtc.org/experiments/rtp-hdrext/video-content-type",
+            // RFC 7273 companion extension: lets receivers tie RTP timestamps
+            // back to the reference clock advertised in `a=ts-refclk`/`a=mediaclk`.
+            "http://www.webrtc.org/experiments/rtp-hdrext/abs-capture-time",
         ];
 
         for extension in extensions {
@@ -111,46 +320,111 @@ impl MediaSetup {
         let mut registry = Registry::new();
         registry = register_default_interceptors(registry, &mut media_engine).unwrap();
 
+        // An SFU only ever needs UDP candidates; restricting NetworkType avoids
+        // wasting time gathering TCP candidates that will never be used.
+        let mut setting_engine = SettingEngine::default();
+        setting_engine.set_network_types(vec![
+            webrtc::ice::network_type::NetworkType::Udp4,
+            webrtc::ice::network_type::NetworkType::Udp6,
+        ]);
+
+        if let Some(mux) = udp_mux {
+            setting_engine.set_ice_udp_mux(mux);
+        } else if let (Some(min), Some(max)) = (
+            env::var("ICE_UDP_PORT_MIN")
+                .ok()
+                .and_then(|v| v.parse::<u16>().ok()),
+            env::var("ICE_UDP_PORT_MAX")
+                .ok()
+                .and_then(|v| v.parse::<u16>().ok()),
+        ) {
+            // Only meaningful without a shared UDP mux, which already pins
+            // every peer connection to one fixed port.
+            let _ = setting_engine.set_ephemeral_udp_port_range(min, max);
+        }
+
         APIBuilder::new()
             .with_media_engine(media_engine)
             .with_interceptor_registry(registry)
+            .with_setting_engine(setting_engine)
             .build()
     }
 
-    pub fn get_rtc_config() -> RTCConfiguration {
-        let stun_url =
-            env::var("STUN_URL").unwrap_or_else(|_| "stun:stun.l.google.com:19302".to_string());
-
+    pub fn get_rtc_config(ice_config: &IceServerConfig) -> RTCConfiguration {
         RTCConfiguration {
-            ice_servers: vec![RTCIceServer {
-                urls: vec![stun_url],
-                ..Default::default()
-            }],
+            ice_servers: ice_config.ice_servers.clone(),
+            ice_transport_policy: ice_config.ice_transport_policy,
             bundle_policy: RTCBundlePolicy::MaxBundle,
             ..Default::default()
         }
     }
 
+    /// Inserts the session-level `a=ts-refclk`/`a=mediaclk` attributes (RFC
+    /// 7273) into an SDP so every subscriber ties RTP timestamps across a
+    /// user's audio/video (and multiple camera streams) to the same
+    /// reference clock. The RTP timestamp offset itself is left at 0 at
+    /// offer time since payloaders for the publisher's tracks don't exist
+    /// yet; `mark_keyframe_received`-style bookkeeping happens once media
+    /// flows.
+    pub fn apply_clock_signaling(sdp: String, mode: ClockSignalingMode) -> String {
+        let attrs = mode.sdp_lines();
+        if attrs.is_empty() {
+            return sdp;
+        }
+
+        match sdp.find("\r\nm=") {
+            Some(idx) => format!("{}\r\n{}{}", &sdp[..idx], attrs, &sdp[idx + 2..]),
+            None => sdp,
+        }
+    }
+
+    /// Rewrites `a=setup:active` to `a=setup:passive` in a generated SDP
+    /// answer. `webrtc-rs` mirrors an offered `a=setup:actpass` back as
+    /// `active`, but this SFU never dials out as the DTLS client, so left
+    /// alone that would flip the role and the handshake would stall waiting
+    /// for a `ClientHello` neither side sends.
+    pub fn fix_dtls_role(sdp: String) -> String {
+        if sdp.contains("a=setup:active") {
+            sdp.replace("a=setup:active", "a=setup:passive")
+        } else {
+            sdp
+        }
+    }
+
     pub async fn subscribe_to_existing_tracks(
         peer: &crate::peer_manager::Peer,
         user_id: &str,
         room_id: &str,
         tracks: &dashmap::DashMap<
-            (String, String, String, String),
+            (String, String, String, String, String),
             std::sync::Arc<crate::broadcaster::TrackBroadcaster>,
         >,
     ) {
         use std::sync::Arc;
         use tracing::info;
         use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+        use webrtc::rtcp::receiver_report::ReceiverReport;
+        use webrtc::rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+        use webrtc::rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
         use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
         use webrtc::track::track_local::TrackLocal;
 
+        use crate::pb;
+        use crate::quality::{compute_rtt_ms, ConnectionQualityTracker};
+        use crate::stats::OutboundRtpStats;
+
+        if !peer.grant.can_subscribe {
+            tracing::warn!(user_id = %user_id, "[SFU] Grant forbids subscribing; skipping existing tracks");
+            return;
+        }
+
         for track_entry in tracks.iter() {
-            let (t_room, t_user, t_stream, t_track) = track_entry.key();
+            let (t_room, t_user, t_stream, t_track, t_rid) = track_entry.key();
 
-            // Filter: Must be same room, different user
-            if t_room == room_id && t_user != user_id {
+            // Filter: same room, different user, and only the layer that
+            // should be wired into a subscriber by default (other simulcast
+            // layers are picked up later via `SelectLayer`).
+            if t_room == room_id && t_user != user_id && crate::simulcast::is_default_layer(t_rid) {
                 let broadcaster = track_entry.value();
                 // t_stream, t_track, t_user are already &String here
 
@@ -165,41 +439,158 @@ impl MediaSetup {
                     .add_track(Arc::clone(&local_track) as Arc<dyn TrackLocal + Send + Sync>)
                     .await
                 {
+                    let writer_stats = Arc::new(OutboundRtpStats::new());
+                    let writer_quality = Arc::new(ConnectionQualityTracker::new());
+                    let writer_nack_buffer = Arc::new(crate::broadcaster::NackBuffer::new());
+
+                    // Resolved up front (rather than after spawning the RTCP
+                    // reader below) so the reader can answer a NACK with a
+                    // properly payload-typed RTX packet instead of just
+                    // counting it.
+                    let params = rtp_sender.get_parameters().await;
+                    let ssrc = params.encodings.first().map(|e| e.ssrc).unwrap_or(0);
+                    let pt = {
+                        if let Some(codec) = params.rtp_parameters.codecs.first() {
+                            codec.payload_type
+                        } else {
+                            0
+                        }
+                    };
+                    let rtx_payload_type = crate::broadcaster::rtx_payload_type_for(pt);
+                    let rtx_ssrc = ssrc.wrapping_add(1);
+                    let rtx_seq = Arc::new(std::sync::atomic::AtomicU16::new(0));
+
+                    let writer_congestion =
+                        Arc::new(crate::congestion::CongestionController::from_env());
+                    let twcc_extension_id = params
+                        .rtp_parameters
+                        .header_extensions
+                        .iter()
+                        .find(|ext| ext.uri == TWCC_EXTENSION_URI)
+                        .map(|ext| ext.id as u8);
+
                     let sender_clone = rtp_sender.clone();
                     let broadcaster_to_move = broadcaster.clone();
+                    let writer_stats_for_rtcp = writer_stats.clone();
+                    let writer_quality_for_rtcp = writer_quality.clone();
+                    let writer_congestion_for_rtcp = writer_congestion.clone();
+                    let nack_track = local_track.clone();
+                    let nack_buffer_for_rtcp = writer_nack_buffer.clone();
+                    let rtx_seq_for_rtcp = rtx_seq.clone();
+                    let quality_event_tx = peer.event_tx.clone();
+                    let quality_target_user_id = t_user.clone();
+                    let quality_stream_id = t_stream.clone();
                     tokio::spawn(async move {
                         let mut rtcp_buf = vec![0u8; 1500];
+                        let mut last_loss_pct: f32 = 0.0;
                         while let Ok((packets, _)) = sender_clone.read(&mut rtcp_buf).await {
                             for packet in packets {
                                 if packet.as_any().is::<PictureLossIndication>() {
+                                    writer_stats_for_rtcp.record_pli();
                                     broadcaster_to_move.request_keyframe().await;
+                                } else if let Some(nack) =
+                                    packet.as_any().downcast_ref::<TransportLayerNack>()
+                                {
+                                    writer_stats_for_rtcp.record_nack();
+                                    if !broadcaster_to_move.do_retransmission() {
+                                        continue;
+                                    }
+                                    for pair in &nack.nacks {
+                                        for seq in pair.packet_list() {
+                                            match nack_buffer_for_rtcp.get(seq).await {
+                                                Some(packet) => {
+                                                    let resend = match rtx_payload_type {
+                                                        Some(rtx_pt) => {
+                                                            let rtx_sequence_number = rtx_seq_for_rtcp
+                                                                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                                            crate::broadcaster::wrap_as_rtx(
+                                                                &packet,
+                                                                rtx_ssrc,
+                                                                rtx_pt,
+                                                                rtx_sequence_number,
+                                                            )
+                                                        }
+                                                        None => packet,
+                                                    };
+                                                    let _ = nack_track.write_rtp(&resend).await;
+                                                }
+                                                None => {
+                                                    tracing::debug!(
+                                                        seq,
+                                                        "[SFU] NACK for packet no longer in resend buffer"
+                                                    );
+                                                }
+                                            }
+                                        }
+                                    }
+                                } else if let Some(rr) =
+                                    packet.as_any().downcast_ref::<ReceiverReport>()
+                                {
+                                    for report in &rr.reports {
+                                        writer_stats_for_rtcp
+                                            .record_receiver_report(report.total_lost, report.jitter);
+
+                                        let loss_pct = f32::from(report.fraction_lost) / 255.0 * 100.0;
+                                        last_loss_pct = loss_pct;
+                                        let rtt_ms =
+                                            compute_rtt_ms(report.last_sender_report, report.delay)
+                                                .unwrap_or(0);
+
+                                        if let Some(score) =
+                                            writer_quality_for_rtcp.observe(loss_pct, rtt_ms).await
+                                        {
+                                            quality_event_tx
+                                                .emit(pb::sfu::sfu_event::Payload::ConnectionQuality(
+                                                    pb::sfu::ConnectionQualityEvent {
+                                                        target_user_id: quality_target_user_id.clone(),
+                                                        stream_id: quality_stream_id.clone(),
+                                                        score: score as u32,
+                                                    },
+                                                ))
+                                                .await;
+                                        }
+                                    }
+                                } else if let Some(tcc) =
+                                    packet.as_any().downcast_ref::<TransportLayerCc>()
+                                {
+                                    writer_congestion_for_rtcp
+                                        .on_feedback(tcc, last_loss_pct)
+                                        .await;
                                 }
                             }
                         }
                     });
 
-                    let params = rtp_sender.get_parameters().await;
-                    let ssrc = params.encodings.first().map(|e| e.ssrc).unwrap_or(0);
-
-                    let pt = {
-                        if let Some(codec) = params.rtp_parameters.codecs.first() {
-                            codec.payload_type
-                        } else {
-                            0
-                        }
-                    };
-
                     info!(
                         "[SFU] subscribe_to_existing_tracks: Resolved PT: {}, SSRC: {}",
                         pt, ssrc
                     );
                     broadcaster
-                        .add_writer(local_track, t_track.clone(), ssrc, pt)
+                        .add_writer(
+                            local_track,
+                            ssrc,
+                            pt,
+                            user_id.to_string(),
+                            writer_stats,
+                            writer_quality,
+                            peer.pc.clone(),
+                            rtp_sender,
+                            writer_nack_buffer,
+                            writer_congestion,
+                            twcc_extension_id,
+                        )
                         .await;
 
                     // delayed Keyframe Request
                     broadcaster.clone().schedule_pli_retry();
                     peer.track_mapping.insert(t_stream.clone(), t_user.clone());
+                    peer.subscribed_tracks.lock().await.insert((
+                        t_room.clone(),
+                        t_user.clone(),
+                        t_stream.clone(),
+                        t_track.clone(),
+                        t_rid.clone(),
+                    ));
                     info!(
                         track = %t_track,
                         user = %t_user,
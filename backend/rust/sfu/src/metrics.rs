@@ -1,7 +1,7 @@
 use lazy_static::lazy_static;
 use prometheus::{
-    register_int_counter, register_int_counter_vec, register_int_gauge, IntCounter, IntCounterVec,
-    IntGauge,
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 lazy_static! {
@@ -36,6 +36,15 @@ lazy_static! {
         "Total number of WebRTC connection failures"
     )
     .unwrap();
+    /// Smoothed 1-5 uplink quality score per session (see
+    /// `crate::sfu_service::spawn_session_stats_collector`), or 0 while a
+    /// peer hasn't sent enough RTP to sample loss/RTT from yet.
+    pub static ref SFU_SESSION_QUALITY_SCORE: IntGaugeVec = register_int_gauge_vec!(
+        "sfu_session_quality_score",
+        "Smoothed connection-quality score (1-5, 0 = unknown) for a peer's own uplink",
+        &["room_id", "user_id"]
+    )
+    .unwrap();
 }
 
 pub fn register_metrics() {
@@ -49,6 +58,9 @@ pub fn register_metrics() {
     let _ = SFU_KEYFRAMES_REQUESTED_TOTAL.get();
     let _ = SFU_WEBRTC_CONNECTIONS_TOTAL.get();
     let _ = SFU_WEBRTC_CONNECTION_FAILURES_TOTAL.get();
+    let _ = SFU_SESSION_QUALITY_SCORE
+        .with_label_values(&["none", "none"])
+        .get();
 }
 
 #[cfg(test)]
@@ -1,34 +1,183 @@
-use crate::pb::sfu::SfuEvent;
+use crate::pb::sfu::sfu_event::Payload as EventPayload;
 use dashmap::DashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tracing::info;
 use webrtc::peer_connection::RTCPeerConnection;
 
+/// A description-mutating operation against a peer's `RTCPeerConnection`,
+/// queued so at most one runs at a time (see [`Peer::spawn_operations_queue`]).
+pub enum NegotiationOp {
+    /// A track was added to this peer's local PC; renegotiate once every
+    /// `Renegotiate` op enqueued in the same burst has been folded in, so
+    /// several tracks added back-to-back produce one offer instead of one
+    /// per track. `track_event`, if set, is announced to the client first.
+    Renegotiate {
+        track_event: Option<crate::pb::signaling::TrackAddedEvent>,
+    },
+    /// An arbitrary signaling-state mutation — applying an inbound SDP
+    /// answer, or an inbound offer followed by generating an answer — run
+    /// in order with the above instead of racing it.
+    Apply(Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>),
+}
+
 // Peer wraps the WebRTC Connection
 pub struct Peer {
     pub pc: Arc<RTCPeerConnection>,
     pub user_id: String,
     pub room_id: String,
+    /// Distinguishes this connection attempt from a prior/concurrent one for
+    /// the same `(room_id, user_id)` in `PeerMap`, so a reconnect or second
+    /// tab gets its own entry instead of clobbering the live one. Generated
+    /// in `create_session` and echoed back in `CreateSessionResponse`.
+    pub session_id: String,
     // Channel to send events (TrackAdded, Renegotiation) to Go -> Frontend
     pub event_tx: crate::types::SharedEventSender,
     // Map from StreamID (in this peer's PC) to Source UserID
     pub track_mapping: Arc<DashMap<String, String>>,
     // Ensure only one negotiation happens at a time per peer
     pub signaling_lock: Arc<Mutex<()>>,
+    /// Enqueues description-mutating operations; drained strictly in order
+    /// by the task spawned in `Peer::new`.
+    pub ops_tx: mpsc::UnboundedSender<NegotiationOp>,
+    /// Set once this peer is being torn down, so ops already in the queue
+    /// are dropped instead of touching a closing/closed `RTCPeerConnection`.
+    closing: Arc<AtomicBool>,
+    /// Remote trickle-ICE candidates that arrived before the remote
+    /// description was set. Drained into `pc.add_ice_candidate` as soon as
+    /// `set_remote_description` succeeds, instead of being dropped.
+    pub pending_ice_candidates: Arc<Mutex<Vec<webrtc::ice_transport::ice_candidate::RTCIceCandidateInit>>>,
+    /// `TrackKey`s this peer has published, so teardown can drop exactly
+    /// these entries from the global track map instead of scanning it for a
+    /// room/user match.
+    pub published_tracks: Arc<Mutex<std::collections::HashSet<crate::types::TrackKey>>>,
+    /// `TrackKey`s this peer subscribes to (i.e. for which it's registered a
+    /// writer on someone else's `TrackBroadcaster`), so teardown can remove
+    /// exactly these writers instead of leaving them forwarding RTP into a
+    /// closed `RTCPeerConnection` forever.
+    pub subscribed_tracks: Arc<Mutex<std::collections::HashSet<crate::types::TrackKey>>>,
+    /// Broadcasts this session's periodic `RTCPeerConnection` stats snapshot;
+    /// every concurrent `GetSessionStats` caller subscribes independently.
+    pub stats_tx: tokio::sync::broadcast::Sender<crate::pb::sfu::SessionStatsSnapshot>,
+    /// Handle for the background task populated by `spawn_session_stats_collector`,
+    /// so teardown can abort it instead of leaving it polling a closed `pc`.
+    pub stats_task: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Set while a `Disconnected` grace/ICE-restart attempt is in flight, so
+    /// a flapping connection state doesn't spawn overlapping grace timers.
+    pub reconnecting: Arc<AtomicBool>,
+    /// The access grant this peer joined with (see `crate::auth`), checked
+    /// before wiring this peer's published tracks to other subscribers and
+    /// before subscribing this peer to others' tracks.
+    pub grant: crate::auth::RoomGrant,
+    /// The lease TTL this session was created with (`CreateSessionRequest::ttl_seconds`).
+    /// `0` means the session never expires on its own; a `KeepAlive` ping
+    /// re-arms `lease_expires_at` by this many seconds again.
+    ttl_seconds: AtomicU64,
+    /// When this session's lease expires, if it has one. The liveness sweep
+    /// reaps a peer past this deadline exactly like a lost `RTCPeerConnection`.
+    pub lease_expires_at: Arc<Mutex<Option<Instant>>>,
 }
 
 impl Peer {
-    pub fn new(pc: Arc<RTCPeerConnection>, user_id: String, room_id: String) -> Self {
+    pub fn new(
+        pc: Arc<RTCPeerConnection>,
+        user_id: String,
+        room_id: String,
+        session_id: String,
+        grant: crate::auth::RoomGrant,
+    ) -> Self {
+        let event_tx = Arc::new(crate::types::EventStream::new());
+        let signaling_lock = Arc::new(Mutex::new(()));
+        let closing = Arc::new(AtomicBool::new(false));
+        let (ops_tx, ops_rx) = mpsc::unbounded_channel();
+        let (stats_tx, _) = tokio::sync::broadcast::channel(8);
+
+        spawn_operations_queue(
+            ops_rx,
+            pc.clone(),
+            event_tx.clone(),
+            user_id.clone(),
+            signaling_lock.clone(),
+            closing.clone(),
+        );
+
         Self {
             pc,
             user_id,
             room_id,
-            event_tx: Arc::new(Mutex::new(None)),
+            session_id,
+            event_tx,
             track_mapping: Arc::new(DashMap::new()),
-            signaling_lock: Arc::new(Mutex::new(())),
+            signaling_lock,
+            ops_tx,
+            closing,
+            pending_ice_candidates: Arc::new(Mutex::new(Vec::new())),
+            published_tracks: Arc::new(Mutex::new(std::collections::HashSet::new())),
+            subscribed_tracks: Arc::new(Mutex::new(std::collections::HashSet::new())),
+            stats_tx,
+            stats_task: Arc::new(Mutex::new(None)),
+            reconnecting: Arc::new(AtomicBool::new(false)),
+            grant,
+            ttl_seconds: AtomicU64::new(0),
+            lease_expires_at: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Marks this peer as closing, so any operations already sitting in its
+    /// queue are dropped rather than run against a dead `RTCPeerConnection`.
+    /// Call this when removing the peer from `MySfu::peers`.
+    pub fn mark_closing(&self) {
+        self.closing.store(true, Ordering::Relaxed);
+    }
+
+    /// Arms this session's lease with `ttl_seconds`, remembering it so later
+    /// `KeepAlive` pings can refresh the same duration. `0` clears the lease
+    /// so the session never expires on its own.
+    pub async fn set_lease_ttl(&self, ttl_seconds: u64) {
+        self.ttl_seconds.store(ttl_seconds, Ordering::Relaxed);
+        self.refresh_lease().await;
+    }
+
+    /// Pushes this session's lease expiry back out by its configured
+    /// `ttl_seconds` from now. A no-op (stays unexpiring) if the session was
+    /// created with `ttl_seconds = 0`.
+    pub async fn refresh_lease(&self) {
+        let ttl = self.ttl_seconds.load(Ordering::Relaxed);
+        let mut lease = self.lease_expires_at.lock().await;
+        *lease = if ttl == 0 {
+            None
+        } else {
+            Some(Instant::now() + Duration::from_secs(ttl))
+        };
+    }
+
+    /// The lease TTL this session was created with, for echoing back in a
+    /// `KeepAliveResponse`.
+    pub fn ttl_seconds(&self) -> u64 {
+        self.ttl_seconds.load(Ordering::Relaxed)
+    }
+
+    /// Queues a renegotiation, optionally announcing `track_event` first.
+    /// Several calls made back-to-back (e.g. many publishers joining at
+    /// once) are coalesced by the operations queue into a single offer.
+    pub fn enqueue_renegotiation(&self, track_event: Option<crate::pb::signaling::TrackAddedEvent>) {
+        let _ = self.ops_tx.send(NegotiationOp::Renegotiate { track_event });
+    }
+
+    /// Queues an arbitrary signaling-state mutation (applying an inbound
+    /// SDP answer/offer) so it runs in order with queued renegotiations
+    /// instead of racing them.
+    pub fn enqueue_apply<F>(&self, op: F)
+    where
+        F: FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + 'static,
+    {
+        let _ = self.ops_tx.send(NegotiationOp::Apply(Box::new(op)));
+    }
+
     pub fn register_ice_candidate_handler(&self) {
         let event_tx_clone = self.event_tx.clone();
         let user_id_ice_candidate = self.user_id.clone();
@@ -43,21 +192,73 @@ impl Peer {
                         tracing::info!(user_id = %user_id_inner, "[SFU] Generated ICE candidate");
                         let candidate_json =
                             serde_json::to_string(&candidate.to_json().unwrap()).unwrap();
-                        let mut tx_lock = event_tx_inner.lock().await;
-                        if let Some(tx) = tx_lock.as_mut() {
-                            let _ = tx
-                                .send(Ok(SfuEvent {
-                                    payload: Some(
-                                        crate::pb::sfu::sfu_event::Payload::IceCandidate(
-                                            candidate_json,
-                                        ),
-                                    ),
-                                }))
-                                .await;
-                        }
+                        event_tx_inner
+                            .emit(EventPayload::IceCandidate(candidate_json))
+                            .await;
                     }
                 })
             },
         ));
     }
 }
+
+/// Drains `ops_rx` strictly in order, running at most one operation at a
+/// time against `pc`. Exits once every [`Peer::ops_tx`] clone has been
+/// dropped (the peer was removed and every in-flight task referencing it
+/// has finished).
+fn spawn_operations_queue(
+    mut ops_rx: mpsc::UnboundedReceiver<NegotiationOp>,
+    pc: Arc<RTCPeerConnection>,
+    event_tx: crate::types::SharedEventSender,
+    user_id: String,
+    signaling_lock: Arc<Mutex<()>>,
+    closing: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        while let Some(op) = ops_rx.recv().await {
+            if closing.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            let _guard = signaling_lock.lock().await;
+            match op {
+                NegotiationOp::Renegotiate { track_event } => {
+                    let mut track_events = vec![track_event];
+
+                    // Coalesce any further renegotiation triggers already
+                    // queued behind this one into the same offer. Stop
+                    // coalescing at the first `Apply` op and run it only
+                    // after this offer has gone out, since that's an inbound
+                    // signal that must stay in order.
+                    let mut next_apply = None;
+                    while let Ok(next) = ops_rx.try_recv() {
+                        match next {
+                            NegotiationOp::Renegotiate { track_event } => {
+                                track_events.push(track_event);
+                            }
+                            NegotiationOp::Apply(f) => {
+                                next_apply = Some(f);
+                                break;
+                            }
+                        }
+                    }
+
+                    for event in track_events.into_iter().flatten() {
+                        event_tx.emit(EventPayload::TrackEvent(event)).await;
+                    }
+
+                    crate::signaling_handler::create_and_send_offer(&pc, &event_tx, &user_id)
+                        .await;
+
+                    if let Some(f) = next_apply {
+                        f().await;
+                    }
+                }
+                NegotiationOp::Apply(f) => {
+                    f().await;
+                }
+            }
+        }
+        info!(%user_id, "[SFU] Operations queue drained, peer torn down");
+    });
+}
@@ -0,0 +1,102 @@
+//! Connection-quality scoring (1-4, mirroring common media-server grading)
+//! for a single subscriber's forwarding leg of a published track. Scores are
+//! derived from RTCP Receiver Report loss/RTT sampled in the same read loop
+//! that already watches for PLI/NACK (see [`crate::track_handler`]), and use
+//! hysteresis so a momentarily noisy link doesn't flap the reported score.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Minimum time a newly observed bucket must hold before it's reported, so a
+/// single noisy RTCP report doesn't flip a client's "poor connection" badge
+/// on and off.
+const HYSTERESIS_WINDOW: Duration = Duration::from_secs(2);
+
+fn bucket_for(loss_pct: f32, rtt_ms: u32) -> u8 {
+    if loss_pct > 10.0 || rtt_ms > 500 {
+        1
+    } else if loss_pct > 5.0 || rtt_ms > 350 {
+        2
+    } else if loss_pct > 2.0 || rtt_ms > 150 {
+        3
+    } else {
+        4
+    }
+}
+
+/// Tracks the last-reported score for one subscriber's view of one track.
+pub struct ConnectionQualityTracker {
+    reported_score: AtomicU8,
+    pending: Mutex<Option<(u8, Instant)>>,
+}
+
+impl ConnectionQualityTracker {
+    pub fn new() -> Self {
+        Self {
+            reported_score: AtomicU8::new(0),
+            pending: Mutex::new(None),
+        }
+    }
+
+    /// Folds in a fresh loss/RTT sample. Returns `Some(score)` the instant a
+    /// new bucket has held steady for `HYSTERESIS_WINDOW`; otherwise `None`
+    /// (no change, or the candidate bucket is still settling).
+    pub async fn observe(&self, loss_pct: f32, rtt_ms: u32) -> Option<u8> {
+        let candidate = bucket_for(loss_pct, rtt_ms);
+        let reported = self.reported_score.load(Ordering::Relaxed);
+
+        if candidate == reported {
+            *self.pending.lock().await = None;
+            return None;
+        }
+
+        let mut pending = self.pending.lock().await;
+        match *pending {
+            Some((score, since)) if score == candidate => {
+                if since.elapsed() >= HYSTERESIS_WINDOW {
+                    *pending = None;
+                    self.reported_score.store(candidate, Ordering::Relaxed);
+                    Some(candidate)
+                } else {
+                    None
+                }
+            }
+            _ => {
+                *pending = Some((candidate, Instant::now()));
+                None
+            }
+        }
+    }
+}
+
+impl Default for ConnectionQualityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes round-trip time from a Receiver Report's LSR (last SR) and DLSR
+/// (delay since last SR) fields, per RFC 3550 A.8. `lsr`/`dlsr` are both in
+/// Q16.16 (1/65536 sec) units, matching the wire format's "middle 32 bits of
+/// NTP timestamp" encoding. Returns `None` if the receiver hasn't seen one of
+/// our Sender Reports yet (`lsr == 0`).
+pub fn compute_rtt_ms(lsr: u32, dlsr: u32) -> Option<u32> {
+    if lsr == 0 {
+        return None;
+    }
+    let now = ntp_short_now();
+    let rtt_fixed = now.wrapping_sub(lsr).wrapping_sub(dlsr);
+    Some(((u64::from(rtt_fixed) * 1000) >> 16) as u32)
+}
+
+/// Current wallclock time as the middle 32 bits of an NTP timestamp.
+fn ntp_short_now() -> u32 {
+    const UNIX_TO_NTP_EPOCH_SECS: u64 = 2_208_988_800;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs().wrapping_add(UNIX_TO_NTP_EPOCH_SECS);
+    let frac = (u64::from(now.subsec_nanos()) << 32) / 1_000_000_000;
+    (((secs & 0xffff) as u32) << 16) | ((frac >> 16) as u32)
+}
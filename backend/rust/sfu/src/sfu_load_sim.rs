@@ -1,11 +1,17 @@
 use bytes::Bytes;
-use sfu::broadcaster::{BroadcasterWriter, TrackBroadcaster};
-use sfu::media_setup::MediaSetup;
+use sfu::broadcaster::{NackBuffer, TrackBroadcaster};
+use sfu::congestion::CongestionController;
+use sfu::media_setup::{IceServerConfig, MediaSetup};
+use sfu::quality::ConnectionQualityTracker;
+use sfu::stats::OutboundRtpStats;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 use webrtc::rtp::header::Header;
 use webrtc::rtp::packet::Packet;
+use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use webrtc::track::track_local::TrackLocal;
 
 fn main() {
     // We use a custom runtime to ensure we control the threads
@@ -14,12 +20,12 @@ fn main() {
         println!("🔥 Starting SFU Logic Load Simulator...");
         println!("   - Configuring WebRTC API...");
 
-        let api = MediaSetup::create_webrtc_api();
-        let config = MediaSetup::get_rtc_config();
+        let api = MediaSetup::create_webrtc_api(None);
+        let config = MediaSetup::get_rtc_config(&IceServerConfig::from_env());
 
         // create a dummy peer connection for the broadcaster source
         let pc = api
-            .new_peer_connection(config)
+            .new_peer_connection(config.clone())
             .await
             .expect("Failed to create PC");
 
@@ -32,18 +38,45 @@ fn main() {
 
         println!("   - Adding 500 subscribers...");
 
-        // Add 500 dummy subscribers
-        let mut writers = broadcaster.writers.write().await;
-        for i in 0..500 {
-            // Channel size 100 to simulate real buffer
-            let (tx, _rx) = tokio::sync::mpsc::channel(100);
-            writers.push(BroadcasterWriter {
-                tx,
-                ssrc: 1000 + i,
-                payload_type: 96,
-            });
+        // Every subscriber's local track lives on one shared downstream PC,
+        // the same setup `benches/sfu_benchmarks.rs` uses to exercise
+        // `add_writer`'s real fan-out path instead of a hand-built writer.
+        let downstream_pc = Arc::new(
+            api.new_peer_connection(config)
+                .await
+                .expect("Failed to create downstream PC"),
+        );
+
+        for i in 0..500u32 {
+            let track = Arc::new(TrackLocalStaticRTP::new(
+                RTCRtpCodecCapability {
+                    mime_type: "video/VP8".to_owned(),
+                    ..Default::default()
+                },
+                format!("track-{}", i),
+                "stream-load-sim".to_owned(),
+            ));
+            let sender = downstream_pc
+                .add_track(track.clone() as Arc<dyn TrackLocal + Send + Sync>)
+                .await
+                .expect("Failed to add track");
+
+            broadcaster
+                .add_writer(
+                    track,
+                    1000 + i,
+                    96,
+                    format!("subscriber-{}", i),
+                    Arc::new(OutboundRtpStats::new()),
+                    Arc::new(ConnectionQualityTracker::new()),
+                    downstream_pc.clone(),
+                    sender,
+                    Arc::new(NackBuffer::new()),
+                    Arc::new(CongestionController::from_env()),
+                    None,
+                )
+                .await;
         }
-        drop(writers); // Release lock
 
         println!("   - Starting broadcast loop (30 seconds)...");
         println!("   - Simulating 60 FPS video traffic...");
@@ -69,7 +102,7 @@ fn main() {
             packet.header.timestamp = packet.header.timestamp.wrapping_add(3000); // 90khz clock
 
             // Hot Path: Broadcast to 500 subs
-            broadcaster.broadcast(&mut packet).await;
+            broadcaster.broadcast(&packet);
             count += 1;
 
             if count % 1000 == 0 {
@@ -1,37 +1,25 @@
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 use webrtc::ice_transport::ice_gathering_state::RTCIceGatheringState;
 use webrtc::peer_connection::RTCPeerConnection;
 
-use crate::pb::{
-    self,
-    sfu::{sfu_event::Payload as EventPayload, SfuEvent},
-};
-
-pub async fn perform_renegotiation(
-    peer_pc: Arc<RTCPeerConnection>,
-    event_tx: crate::types::SharedEventSender,
-    user_id: String,
-    signaling_lock: Arc<Mutex<()>>,
-    track_mapping_event: Option<pb::signaling::TrackAddedEvent>,
+use crate::media_setup::{ClockSignalingMode, MediaSetup};
+use crate::pb::sfu::sfu_event::Payload as EventPayload;
+use crate::types::SharedEventSender;
+
+/// Creates an offer, sets it as the local description, waits (briefly) for
+/// ICE gathering, and sends the resulting SDP to the client over its event
+/// channel.
+///
+/// Callers must already hold the peer's `signaling_lock` — in practice this
+/// only runs from `Peer`'s operations queue (see `crate::peer_manager`),
+/// which serializes it against every other description-mutating op for the
+/// same peer.
+pub async fn create_and_send_offer(
+    peer_pc: &Arc<RTCPeerConnection>,
+    event_tx: &SharedEventSender,
+    user_id: &str,
 ) {
-    let _guard = signaling_lock.lock().await;
-
-    // A. Add track mapping if provided
-    if let Some(event) = track_mapping_event {
-        let mut tx_lock = event_tx.lock().await;
-        if let Some(tx) = tx_lock.as_mut() {
-            let _ = tx
-                .send(Ok(SfuEvent {
-                    payload: Some(EventPayload::TrackEvent(event)),
-                }))
-                .await;
-            println!("[SFU] TrackAdded event sent to channel for {}", user_id);
-        }
-    }
-
-    // B. Create Offer
     let offer = match peer_pc.create_offer(None).await {
         Ok(o) => o,
         Err(e) => {
@@ -56,19 +44,14 @@ pub async fn perform_renegotiation(
         .await;
     }
 
-    // C. Send Offer
     let local_desc = peer_pc.local_description().await.unwrap_or_default();
-    info!(user_id = %user_id, sdp_length = %local_desc.sdp.len(), "[SFU] Sending Renegotiation Offer");
+    let sdp = MediaSetup::apply_clock_signaling(local_desc.sdp, ClockSignalingMode::from_env());
+    info!(user_id = %user_id, sdp_length = %sdp.len(), "[SFU] Sending Renegotiation Offer");
 
-    let mut tx_lock = event_tx.lock().await;
-    if let Some(tx) = tx_lock.as_mut() {
-        let _ = tx
-            .send(Ok(SfuEvent {
-                payload: Some(EventPayload::RenegotiateSdpOffer(local_desc.sdp)),
-            }))
-            .await;
+    if event_tx.has_sender() {
         debug!(user_id = %user_id, "[SFU] Renegotiation message sent to channel");
     } else {
         warn!(user_id = %user_id, "[SFU] !! Event channel is CLOSED or None");
     }
+    event_tx.emit(EventPayload::RenegotiateSdpOffer(sdp)).await;
 }
@@ -0,0 +1,100 @@
+//! Shared simulcast layer-selection helpers.
+//!
+//! Simulcast publishers send up to three RID-tagged encodings for the same
+//! logical track (conventionally `"f"`/`"h"`/`"q"` for full/half/quarter
+//! resolution). webrtc-rs fires `on_track` once per encoding, each with its
+//! own SSRC, so the SFU stores one [`crate::broadcaster::TrackBroadcaster`]
+//! per `(room, user, stream, track, rid)` (see [`crate::types::TrackKey`])
+//! and only wires the default layer into new subscribers; the other layers
+//! sit idle as candidate sources until a client switches via `SelectLayer`.
+
+/// The RID webrtc-rs (and simulcast-capable browsers) assign the
+/// full-resolution encoding.
+pub const DEFAULT_RID: &str = "f";
+
+/// Conventional simulcast RIDs, ordered from highest to lowest quality.
+const LAYER_ORDER: [&str; 3] = ["f", "h", "q"];
+
+/// Whether `rid` is the layer that should be wired into a subscriber's
+/// connection the moment a publisher's track is discovered: either a
+/// non-simulcast track (empty RID) or the full-resolution simulcast layer.
+/// Other layers are registered as broadcasters but left unselected until a
+/// `SelectLayer` request asks the SFU to switch a subscriber onto them.
+pub fn is_default_layer(rid: &str) -> bool {
+    rid.is_empty() || rid == DEFAULT_RID
+}
+
+/// The next lower-quality RID after `current`, if any. Returns `None` for an
+/// unrecognized RID (e.g. a non-simulcast track) or if `current` is already
+/// the lowest known layer.
+pub fn lower_rid(current: &str) -> Option<&'static str> {
+    let pos = LAYER_ORDER.iter().position(|&r| r == current)?;
+    LAYER_ORDER.get(pos + 1).copied()
+}
+
+/// The next higher-quality RID before `current`, if any. Returns `None` for
+/// an unrecognized RID or if `current` is already the highest known layer.
+/// Symmetric to [`lower_rid`]; used to recover a subscriber back up once
+/// `crate::bandwidth::BandwidthEstimator` reports sustained low loss.
+pub fn higher_rid(current: &str) -> Option<&'static str> {
+    let pos = LAYER_ORDER.iter().position(|&r| r == current)?;
+    if pos == 0 {
+        return None;
+    }
+    LAYER_ORDER.get(pos - 1).copied()
+}
+
+/// Moves `subscriber_user_id`'s writer from whichever of `candidates` it's
+/// currently attached to onto `new_rid`'s broadcaster, replaying that
+/// layer's cached keyframe (or requesting one if none is cached) so the new
+/// layer starts clean. Used by both the explicit `SelectLayer`
+/// signal and the automatic sustained-loss downgrade in `track_handler`.
+///
+/// `candidates` is every sibling broadcaster for one publisher's stream,
+/// paired with its RID.
+pub async fn switch_subscriber_layer(
+    candidates: &[(String, std::sync::Arc<crate::broadcaster::TrackBroadcaster>)],
+    subscriber_user_id: &str,
+    new_rid: &str,
+) -> Result<(), &'static str> {
+    let Some((_, new_broadcaster)) = candidates.iter().find(|(rid, _)| rid == new_rid) else {
+        return Err("no broadcaster for requested layer");
+    };
+
+    let mut moved_writer = None;
+    for (rid, broadcaster) in candidates {
+        if rid == new_rid {
+            continue;
+        }
+        if let Some(writer) = broadcaster.remove_writer(subscriber_user_id).await {
+            moved_writer = Some(writer);
+            break;
+        }
+    }
+
+    let Some(writer) = moved_writer else {
+        return Err("subscriber had no existing writer on any other layer");
+    };
+
+    // Re-registering spawns a fresh consumer task subscribed to the new
+    // layer's fan-out channel and replays its cached keyframe (or requests
+    // one if none is cached), so the subscriber's decoder can recover
+    // immediately. Dropping `writer`
+    // afterwards aborts its old task.
+    new_broadcaster
+        .add_writer(
+            writer.track.clone(),
+            writer.ssrc,
+            writer.payload_type,
+            writer.subscriber_user_id.clone(),
+            writer.stats.clone(),
+            writer.quality.clone(),
+            writer.downstream_pc.clone(),
+            writer.sender.clone(),
+            writer.nack_buffer.clone(),
+            writer.congestion.clone(),
+            writer.twcc_extension_id,
+        )
+        .await;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Rolling RTP forwarding statistics, one `InboundRtpStats` per published
+//! track and one `OutboundRtpStats` per subscriber writer of that track.
+//! Counters are plain atomics so the hot forwarding path in
+//! [`crate::broadcaster`] and [`crate::track_handler`] can update them without
+//! locking; [`GetStats`](crate::sfu_service::MySfu::get_stats) only reads a
+//! consistent-enough snapshot for reporting, not for forwarding decisions.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Interarrival jitter state (RFC 3550 §6.4.1), updated under a small lock
+/// since it needs a coherent (arrival instant, RTP timestamp) pair rather
+/// than two independently-racing atomics.
+#[derive(Default)]
+struct JitterState {
+    last_arrival: Option<Instant>,
+    last_rtp_timestamp: u32,
+}
+
+/// Stats for the media a publisher sends into the SFU.
+pub struct InboundRtpStats {
+    pub packets_received: AtomicU64,
+    pub bytes_received: AtomicU64,
+    pub packets_lost: AtomicU64,
+    last_sequence_number: AtomicU32,
+    has_sequence_number: AtomicBool,
+    /// RTP clock rate (e.g. 90000 for video, 48000 for Opus) the jitter
+    /// estimate converts elapsed wall-clock time into, to compare against
+    /// the packet's RTP timestamp.
+    clock_rate: u32,
+    jitter_state: Mutex<JitterState>,
+    /// Smoothed interarrival jitter estimate, in RTP timestamp units.
+    jitter: AtomicU32,
+    /// Unix epoch millis of the last packet `mark_keyframe_received` was
+    /// called for, mirrored here so `GetStats` can report it without
+    /// reaching back into `TrackBroadcaster`.
+    last_keyframe_ts_ms: AtomicI64,
+}
+
+impl InboundRtpStats {
+    pub fn new(clock_rate: u32) -> Self {
+        Self {
+            packets_received: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            packets_lost: AtomicU64::new(0),
+            last_sequence_number: AtomicU32::new(0),
+            has_sequence_number: AtomicBool::new(false),
+            clock_rate: clock_rate.max(1),
+            jitter_state: Mutex::new(JitterState::default()),
+            jitter: AtomicU32::new(0),
+            last_keyframe_ts_ms: AtomicI64::new(0),
+        }
+    }
+
+    /// Records one received RTP packet and estimates loss from sequence
+    /// number gaps. This is a cheap heuristic, not a jitter-buffer-accurate
+    /// count: a gap bigger than 1 (and not a large backward jump, which
+    /// `wrapping_sub` would also report as "big") is assumed to mean
+    /// `gap - 1` packets went missing in between.
+    pub fn record_packet(&self, sequence_number: u16, payload_len: usize) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(payload_len as u64, Ordering::Relaxed);
+
+        if self.has_sequence_number.swap(true, Ordering::AcqRel) {
+            let prev = self.last_sequence_number.load(Ordering::Relaxed);
+            let gap = sequence_number.wrapping_sub(prev);
+            if gap > 1 && gap < u16::MAX / 2 {
+                self.packets_lost
+                    .fetch_add(u64::from(gap - 1), Ordering::Relaxed);
+            }
+        }
+        self.last_sequence_number
+            .store(sequence_number, Ordering::Relaxed);
+    }
+
+    /// Updates the RFC 3550 interarrival jitter estimate from this packet's
+    /// RTP timestamp and its arrival instant. Kept separate from
+    /// `record_packet` so callers without a reliable arrival instant (tests,
+    /// replays) can skip it.
+    pub async fn record_arrival(&self, rtp_timestamp: u32) {
+        let now = Instant::now();
+        let mut state = self.jitter_state.lock().await;
+        if let Some(last_arrival) = state.last_arrival {
+            let arrival_units =
+                (now - last_arrival).as_secs_f64() * f64::from(self.clock_rate);
+            let timestamp_delta =
+                f64::from(rtp_timestamp.wrapping_sub(state.last_rtp_timestamp) as i32);
+            let d = (arrival_units - timestamp_delta).abs();
+
+            let prev_jitter = f64::from(self.jitter.load(Ordering::Relaxed));
+            let new_jitter = prev_jitter + (d - prev_jitter) / 16.0;
+            self.jitter.store(new_jitter as u32, Ordering::Relaxed);
+        }
+        state.last_arrival = Some(now);
+        state.last_rtp_timestamp = rtp_timestamp;
+    }
+
+    pub fn mark_keyframe_received(&self, unix_ms: i64) {
+        self.last_keyframe_ts_ms.store(unix_ms, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> InboundRtpStatsSnapshot {
+        InboundRtpStatsSnapshot {
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            packets_lost: self.packets_lost.load(Ordering::Relaxed),
+            jitter: self.jitter.load(Ordering::Relaxed),
+            last_keyframe_ts_ms: self.last_keyframe_ts_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InboundRtpStatsSnapshot {
+    pub packets_received: u64,
+    pub bytes_received: u64,
+    pub packets_lost: u64,
+    pub jitter: u32,
+    pub last_keyframe_ts_ms: i64,
+}
+
+/// Stats for the media the SFU forwards to one subscriber writer.
+#[derive(Default)]
+pub struct OutboundRtpStats {
+    pub packets_forwarded: AtomicU64,
+    pub bytes_forwarded: AtomicU64,
+    pub nack_count: AtomicU64,
+    pub pli_count: AtomicU64,
+    // Latest values from the subscriber's RTCP Receiver Reports, not a
+    // running total - they already describe the whole session so far.
+    pub packets_lost: AtomicU64,
+    pub jitter: AtomicU32,
+    pub round_trip_time_ms: AtomicU32,
+    /// Writes to this writer's local track that returned an `Err`, e.g. a
+    /// disconnected subscriber's transport reporting "broken pipe". This
+    /// alone doesn't tear the writer down — `remove_writer`/teardown does
+    /// that once the session actually closes — but a rising rate here is
+    /// what lets an operator spot a failing writer before it gets there.
+    pub send_failures: AtomicU64,
+    /// Unix epoch millis of the last successful forward, or 0 if none yet.
+    /// Read alongside `send_failures` to tell "never sent anything" apart
+    /// from "was healthy, then started failing".
+    pub last_success_ms: AtomicI64,
+}
+
+impl OutboundRtpStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_forwarded(&self, payload_len: usize, unix_ms: i64) {
+        self.packets_forwarded.fetch_add(1, Ordering::Relaxed);
+        self.bytes_forwarded
+            .fetch_add(payload_len as u64, Ordering::Relaxed);
+        self.last_success_ms.store(unix_ms, Ordering::Relaxed);
+    }
+
+    pub fn record_send_failure(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_nack(&self) {
+        self.nack_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_pli(&self) {
+        self.pli_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_receiver_report(&self, packets_lost: u32, jitter: u32) {
+        self.packets_lost
+            .store(u64::from(packets_lost), Ordering::Relaxed);
+        self.jitter.store(jitter, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> OutboundRtpStatsSnapshot {
+        OutboundRtpStatsSnapshot {
+            packets_forwarded: self.packets_forwarded.load(Ordering::Relaxed),
+            bytes_forwarded: self.bytes_forwarded.load(Ordering::Relaxed),
+            nack_count: self.nack_count.load(Ordering::Relaxed),
+            pli_count: self.pli_count.load(Ordering::Relaxed),
+            packets_lost: self.packets_lost.load(Ordering::Relaxed),
+            jitter: self.jitter.load(Ordering::Relaxed),
+            round_trip_time_ms: self.round_trip_time_ms.load(Ordering::Relaxed),
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+            last_success_ms: self.last_success_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutboundRtpStatsSnapshot {
+    pub packets_forwarded: u64,
+    pub bytes_forwarded: u64,
+    pub nack_count: u64,
+    pub pli_count: u64,
+    pub packets_lost: u64,
+    pub jitter: u32,
+    pub round_trip_time_ms: u32,
+    pub send_failures: u64,
+    pub last_success_ms: i64,
+}
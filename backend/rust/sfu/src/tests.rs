@@ -1,11 +1,11 @@
 use super::*;
 use crate::pb::sfu::sfu_service_server::SfuService;
-use crate::pb::sfu::{CreateSessionRequest, ListenRequest};
-use dashmap::DashMap;
+use crate::pb::sfu::{CreateSessionRequest, ListenRequest, SignalMessage};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tonic::Request;
 use webrtc::api::APIBuilder;
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::policy::bundle_policy::RTCBundlePolicy;
 use webrtc::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
@@ -14,18 +14,24 @@ use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 #[tokio::test]
 async fn test_broadcaster_add_writer() {
     let api = APIBuilder::new().build();
-    let pc = api
-        .new_peer_connection(RTCConfiguration::default())
-        .await
-        .unwrap();
-    let pc = Arc::new(pc);
+    let source_pc = Arc::new(
+        api.new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap(),
+    );
+    let downstream_pc = Arc::new(
+        api.new_peer_connection(RTCConfiguration::default())
+            .await
+            .unwrap(),
+    );
 
     let codec = RTCRtpCodecCapability {
         mime_type: "video/VP8".to_owned(),
         ..Default::default()
     };
 
-    let broadcaster = TrackBroadcaster::new("video".to_string(), codec.clone(), pc, 12345);
+    let broadcaster =
+        Arc::new(TrackBroadcaster::new("video".to_string(), codec.clone(), source_pc, 12345));
 
     let track = Arc::new(TrackLocalStaticRTP::new(
         codec,
@@ -33,7 +39,26 @@ async fn test_broadcaster_add_writer() {
         "stream-1".to_owned(),
     ));
 
-    broadcaster.add_writer(track.clone(), 12345, 96).await;
+    let sender = downstream_pc
+        .add_track(track.clone() as Arc<dyn webrtc::track::track_local::TrackLocal + Send + Sync>)
+        .await
+        .unwrap();
+
+    broadcaster
+        .add_writer(
+            track.clone(),
+            12345,
+            96,
+            "subscriber-1".to_string(),
+            Arc::new(crate::stats::OutboundRtpStats::new()),
+            Arc::new(crate::quality::ConnectionQualityTracker::new()),
+            downstream_pc,
+            sender,
+            Arc::new(crate::broadcaster::NackBuffer::new()),
+            Arc::new(crate::congestion::CongestionController::new(50_000, 8_000_000)),
+            None,
+        )
+        .await;
 
     let writers = broadcaster.writers.read().await;
     assert_eq!(writers.len(), 1);
@@ -41,10 +66,7 @@ async fn test_broadcaster_add_writer() {
 
 #[tokio::test]
 async fn test_signaling_flow_and_track_notification() {
-    let sfu = MySfu {
-        peers: Arc::new(DashMap::new()),
-        tracks: Arc::new(DashMap::new()),
-    };
+    let sfu = MySfu::new().await.expect("failed to bind UDP mux");
 
     let room_id = "test-room".to_string();
     let user_a = "user-a".to_string();
@@ -54,6 +76,8 @@ async fn test_signaling_flow_and_track_notification() {
     let req_a = Request::new(CreateSessionRequest {
         user_id: user_a.clone(),
         room_id: room_id.clone(),
+        access_token: String::new(),
+        ttl_seconds: 0,
     });
     let res_a = sfu.create_session(req_a).await.unwrap().into_inner();
     assert!(!res_a.sdp_offer.is_empty());
@@ -62,6 +86,8 @@ async fn test_signaling_flow_and_track_notification() {
     let req_listen_a = Request::new(ListenRequest {
         user_id: user_a.clone(),
         room_id: room_id.clone(),
+        resume_from_sequence: None,
+        session_id: String::new(),
     });
     let mut _stream_a = sfu.listen_events(req_listen_a).await.unwrap().into_inner();
 
@@ -69,6 +95,8 @@ async fn test_signaling_flow_and_track_notification() {
     let req_b = Request::new(CreateSessionRequest {
         user_id: user_b.clone(),
         room_id: room_id.clone(),
+        access_token: String::new(),
+        ttl_seconds: 0,
     });
     let _res_b = sfu.create_session(req_b).await.unwrap().into_inner();
 
@@ -76,6 +104,8 @@ async fn test_signaling_flow_and_track_notification() {
     let req_listen_b = Request::new(ListenRequest {
         user_id: user_b.clone(),
         room_id: room_id.clone(),
+        resume_from_sequence: None,
+        session_id: String::new(),
     });
     let mut _stream_b = sfu.listen_events(req_listen_b).await.unwrap().into_inner();
 
@@ -93,12 +123,9 @@ async fn test_signaling_flow_and_track_notification() {
 }
 #[tokio::test]
 async fn test_webrtc_api_configuration() {
-    let _sfu = MySfu {
-        peers: Arc::new(DashMap::new()),
-        tracks: Arc::new(DashMap::new()),
-    };
+    let _sfu = MySfu::new().await.expect("failed to bind UDP mux");
 
-    let api = MediaSetup::create_webrtc_api();
+    let api = MediaSetup::create_webrtc_api(None);
     let config = RTCConfiguration {
         bundle_policy: RTCBundlePolicy::MaxBundle,
         ..Default::default()
@@ -155,10 +182,7 @@ async fn test_signaling_lock_concurrency() {
 
 #[tokio::test]
 async fn test_subscribe_logic() {
-    let sfu = MySfu {
-        peers: Arc::new(DashMap::new()),
-        tracks: Arc::new(DashMap::new()),
-    };
+    let sfu = MySfu::new().await.expect("failed to bind UDP mux");
 
     let room_id = "room1".to_string();
     let user_a = "userA".to_string();
@@ -166,7 +190,7 @@ async fn test_subscribe_logic() {
     let track_id = "track1".to_string();
 
     // Create a broadcaster for User A
-    let api = MediaSetup::create_webrtc_api();
+    let api = MediaSetup::create_webrtc_api(None);
     let pc_a = Arc::new(
         api.new_peer_connection(RTCConfiguration::default())
             .await
@@ -184,7 +208,13 @@ async fn test_subscribe_logic() {
     ));
 
     sfu.tracks.insert(
-        (room_id.clone(), user_a.clone(), stream_id.clone(), track_id),
+        (
+            room_id.clone(),
+            user_a.clone(),
+            stream_id.clone(),
+            track_id,
+            String::new(),
+        ),
         broadcaster,
     );
 
@@ -194,14 +224,15 @@ async fn test_subscribe_logic() {
             .await
             .unwrap(),
     );
-    let peer_b = Peer {
-        pc: pc_b,
-        user_id: "userB".to_string(),
-        room_id: room_id.clone(),
-        event_tx: Arc::new(Mutex::new(None)),
-        track_mapping: Arc::new(DashMap::new()),
-        signaling_lock: Arc::new(Mutex::new(())),
-    };
+    let peer_b = Peer::new(
+        pc_b,
+        "userB".to_string(),
+        room_id.clone(),
+        "session-b".to_string(),
+        crate::auth::AuthConfig::from_env()
+            .verify("", &room_id, "userB")
+            .unwrap(),
+    );
 
     // Peer B subscribes to existing tracks
     MediaSetup::subscribe_to_existing_tracks(&peer_b, "userB", &room_id, &sfu.tracks).await;
@@ -213,3 +244,45 @@ async fn test_subscribe_logic() {
         &user_a
     );
 }
+
+#[tokio::test]
+async fn test_ice_candidate_buffered_before_remote_description() {
+    let sfu = MySfu::new().await.expect("failed to bind UDP mux");
+
+    let room_id = "ice-buffer-room".to_string();
+    let user_id = "ice-buffer-user".to_string();
+
+    let req = Request::new(CreateSessionRequest {
+        user_id: user_id.clone(),
+        room_id: room_id.clone(),
+        access_token: String::new(),
+        ttl_seconds: 0,
+    });
+    let res = sfu.create_session(req).await.unwrap().into_inner();
+
+    let candidate = RTCIceCandidateInit {
+        candidate: "candidate:1 1 udp 2122260223 192.0.2.1 5000 typ host".to_string(),
+        ..Default::default()
+    };
+    let candidate_json = serde_json::to_string(&candidate).unwrap();
+
+    // No SdpAnswer has been applied yet, so this candidate can't be handed
+    // to the peer connection directly — it must land in the pending queue
+    // instead of being silently dropped.
+    let signal = Request::new(SignalMessage {
+        room_id: room_id.clone(),
+        user_id: user_id.clone(),
+        payload: Some(pb::sfu::signal_message::Payload::IceCandidate(
+            candidate_json,
+        )),
+        session_id: res.session_id.clone(),
+    });
+    sfu.handle_signal(signal).await.unwrap();
+
+    let peer = sfu
+        .peers
+        .get(&(room_id, user_id, res.session_id))
+        .unwrap();
+    assert!(peer.pc.remote_description().await.is_none());
+    assert_eq!(peer.pending_ice_candidates.lock().await.len(), 1);
+}
@@ -3,29 +3,39 @@ use std::sync::Arc;
 use tracing::{debug, error, info, trace, warn};
 use webrtc::peer_connection::RTCPeerConnection;
 use webrtc::rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use webrtc::rtcp::receiver_report::ReceiverReport;
+use webrtc::rtcp::sender_report::SenderReport;
+use webrtc::rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+use webrtc::rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
 use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use webrtc::track::track_local::TrackLocal;
 use webrtc::track::track_remote::TrackRemote;
 
 use crate::broadcaster::TrackBroadcaster;
+use crate::media_setup::ClockSignalingMode;
 use crate::pb;
 use crate::peer_manager::Peer;
-use crate::signaling_handler::perform_renegotiation;
+use crate::quality::{compute_rtt_ms, ConnectionQualityTracker};
+use crate::stats::OutboundRtpStats;
 
 pub fn attach_track_handler(
     pc: &Arc<RTCPeerConnection>,
     user_id: String,
     room_id: String,
-    peers: Arc<DashMap<(String, String), Peer>>,
-    tracks: Arc<DashMap<(String, String, String, String), Arc<TrackBroadcaster>>>,
+    session_id: String,
+    peers: Arc<DashMap<(String, String, String), Peer>>,
+    tracks: Arc<DashMap<(String, String, String, String, String), Arc<TrackBroadcaster>>>,
+    connector: Option<crate::connector::ConnectorHandle>,
 ) {
     let peers_clone = peers.clone();
     let tracks_map = tracks.clone();
     let user_id_clone = user_id.clone();
     let room_id_clone = room_id.clone();
+    let session_id_clone = session_id.clone();
     let pc_for_ontrack = pc.clone();
+    let connector_for_ontrack = connector.clone();
 
-    pc.on_track(Box::new(move |track: Arc<TrackRemote>, _receiver, _transceiver| {
+    pc.on_track(Box::new(move |track: Arc<TrackRemote>, receiver, _transceiver| {
         let track_id = track.id().to_owned();
         let track_kind = track.kind().to_string();
         let track_ssrc = track.ssrc();
@@ -40,37 +50,134 @@ pub fn attach_track_handler(
 
         let user_id = user_id_clone.clone();
         let room_id = room_id_clone.clone();
+        let session_id = session_id_clone.clone();
         let peers = peers_clone.clone();
         let tracks_map = tracks_map.clone();
         let pc_capture = pc_for_ontrack.clone();
+        let connector = connector_for_ontrack.clone();
 
         Box::pin(async move {
             info!(user_id = %user_id, kind = %track.kind(), "Received track from user");
 
+            if let Some(own_peer) = peers.get(&(room_id.clone(), user_id.clone(), session_id.clone())) {
+                if !own_peer.grant.may_publish_as(&user_id) {
+                    warn!(user_id = %user_id, "[SFU] Grant forbids publishing; dropping track");
+                    return;
+                }
+            }
+
             // 1. Create Broadcaster
             let capability = track.codec().capability.clone();
-            let broadcaster = Arc::new(TrackBroadcaster::new(
+            let broadcaster = Arc::new(TrackBroadcaster::with_coalesce_config(
                 track_kind.clone(),
                 capability,
                 pc_capture,
                 track_ssrc,
+                crate::broadcaster::CoalesceConfig::from_env(),
+                track_kind != "audio",
             ));
 
+            let rid = track.rid().to_owned();
+            let is_default_layer = crate::simulcast::is_default_layer(&rid);
+
             let track_key = (
                 room_id.clone(),
                 user_id.clone(),
                 track.stream_id().to_owned(),
                 track.id().to_owned(),
+                rid.clone(),
             );
             info!(?track_key, "[SFU] Created broadcaster for track");
-            tracks_map.insert(track_key, broadcaster.clone());
+            tracks_map.insert(track_key.clone(), broadcaster.clone());
+            let track_key_for_subscribers = track_key.clone();
+            if let Some(own_peer) = peers.get(&(room_id.clone(), user_id.clone(), session_id.clone())) {
+                own_peer.published_tracks.lock().await.insert(track_key);
+            }
+
+            if let Some(connector) = &connector {
+                connector.emit(
+                    crate::connector::ConnectorEvent::new(
+                        crate::connector::ConnectorEventType::TrackAdded,
+                        room_id.clone(),
+                        user_id.clone(),
+                    )
+                    .with_track(track.stream_id().to_owned(), track_kind.clone()),
+                );
+            }
+
+            crate::captions::maybe_spawn(
+                &crate::captions::CaptioningConfig::from_env(),
+                &broadcaster,
+                room_id.clone(),
+                user_id.clone(),
+                track.stream_id().to_owned(),
+                peers.clone(),
+            );
+
+            // 1b. Watch for RFC 7273 clock alignment: the publisher's
+            // inbound RTCP Sender Reports pair an NTP wall-clock time with
+            // this stream's RTP timestamp base. Forward that pairing to
+            // subscribers (once) so they can align playout across streams,
+            // as long as the room has clock signaling enabled.
+            if let Some(refclk) = ClockSignalingMode::from_env().refclk_label() {
+                let broadcaster_for_clock = broadcaster.clone();
+                let peers_for_clock = peers.clone();
+                let room_id_for_clock = room_id.clone();
+                let user_id_for_clock = user_id.clone();
+                let stream_id_for_clock = track.stream_id().to_owned();
+                tokio::spawn(async move {
+                    let mut rtcp_buf = vec![0u8; 1500];
+                    while let Ok((packets, _)) = receiver.read(&mut rtcp_buf).await {
+                        for packet in packets {
+                            if let Some(sr) = packet.as_any().downcast_ref::<SenderReport>() {
+                                if !broadcaster_for_clock
+                                    .record_clock_sync(sr.ntp_time, sr.rtp_time)
+                                    .await
+                                {
+                                    continue;
+                                }
+
+                                let payload = pb::sfu::sfu_event::Payload::ClockSync(
+                                    pb::sfu::ClockSyncEvent {
+                                        stream_id: stream_id_for_clock.clone(),
+                                        refclk: refclk.clone(),
+                                        rtp_offset: sr.rtp_time,
+                                    },
+                                );
+                                for peer_entry in peers_for_clock.iter() {
+                                    let other_peer = peer_entry.value();
+                                    if other_peer.room_id == room_id_for_clock
+                                        && other_peer.user_id != user_id_for_clock
+                                    {
+                                        other_peer
+                                            .event_tx
+                                            .emit(payload.clone())
+                                            .await;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                });
+            }
 
             // 2. Notify Existing Peers & Add Writer to them
-            info!(count = %peers.len(), "[SFU] Notifying peers about new track");
-            for peer_entry in peers.iter() {
-                let other_peer = peer_entry.value();
-                debug!(peer = %other_peer.user_id, matching_room = %other_peer.room_id, "[SFU] Checking peer");
-                if other_peer.room_id == room_id && other_peer.user_id != user_id {
+            //
+            // Only the default layer gets a local track wired into each
+            // subscriber's connection; the other simulcast encodings (if
+            // any) are registered above as candidate sources a subscriber
+            // can switch onto later via `SelectLayer`, but don't need their
+            // own subscriber-facing track.
+            if is_default_layer {
+                info!(count = %peers.len(), "[SFU] Notifying peers about new track");
+                for peer_entry in peers.iter() {
+                    let other_peer = peer_entry.value();
+                    debug!(peer = %other_peer.user_id, matching_room = %other_peer.room_id, "[SFU] Checking peer");
+                    if other_peer.room_id == room_id && other_peer.user_id != user_id {
+                    if !other_peer.grant.can_subscribe {
+                        debug!(peer = %other_peer.user_id, "[SFU] Grant forbids subscribing; not forwarding track");
+                        continue;
+                    }
                     info!(target_user = %other_peer.user_id, "[SFU] Forwarding new track");
 
                     let broadcaster_clone = broadcaster.clone();
@@ -81,11 +188,16 @@ pub fn attach_track_handler(
                     let source_user_id = user_id.clone();
 
                     let other_peer_pc = other_peer.pc.clone();
-                    let other_peer_signaling_lock = other_peer.signaling_lock.clone();
                     let other_peer_event_tx = other_peer.event_tx.clone();
                     let other_peer_track_mapping = other_peer.track_mapping.clone();
+                    let other_peer_subscribed_tracks = other_peer.subscribed_tracks.clone();
                     let other_peer_user_id = other_peer.user_id.clone();
+                    let other_peer_ops_tx = other_peer.ops_tx.clone();
                     let track_for_pt = track.clone();
+                    let rids_tracks_map = tracks_map.clone();
+                    let rids_room_id = room_id.clone();
+                    let connector_for_subscriber = connector.clone();
+                    let subscriber_track_key = track_key_for_subscribers.clone();
 
                     tokio::spawn(async move {
                         let local_track = Arc::new(TrackLocalStaticRTP::new(
@@ -105,95 +217,331 @@ pub fn attach_track_handler(
                             }
                         };
 
+                        let writer_stats = Arc::new(OutboundRtpStats::new());
+                        let writer_quality = Arc::new(ConnectionQualityTracker::new());
+                        let writer_bandwidth = Arc::new(crate::bandwidth::BandwidthEstimator::new());
+                        let writer_nack_buffer = Arc::new(crate::broadcaster::NackBuffer::new());
+
+                        // Resolved up front (rather than after spawning the
+                        // RTCP reader below) so the reader can answer a NACK
+                        // with a properly payload-typed RTX packet instead
+                        // of reusing the primary stream's sequence space.
+                        let params = rtp_sender.get_parameters().await;
+                        let ssrc = params.encodings.first().map(|e| e.ssrc).unwrap_or(0);
+                        let pt = if let Some(codec) = params.rtp_parameters.codecs.first() {
+                            codec.payload_type
+                        } else {
+                            // Fallback to incoming PT if we can't find a negotiated one (better than 0)
+                            let incoming_pt = track_for_pt.payload_type().try_into().unwrap_or(0);
+                            warn!(incoming_pt = %incoming_pt, "[SFU] Outgoing codecs empty, falling back to incoming PT");
+                            incoming_pt
+                        };
+                        let rtx_payload_type = crate::broadcaster::rtx_payload_type_for(pt);
+                        let rtx_ssrc = ssrc.wrapping_add(1);
+                        let rtx_seq = Arc::new(std::sync::atomic::AtomicU16::new(0));
+
+                        let writer_congestion =
+                            Arc::new(crate::congestion::CongestionController::from_env());
+                        let twcc_extension_id = params
+                            .rtp_parameters
+                            .header_extensions
+                            .iter()
+                            .find(|ext| ext.uri == crate::media_setup::TWCC_EXTENSION_URI)
+                            .map(|ext| ext.id as u8);
+
                         let sender_clone = rtp_sender.clone();
                         let broadcaster_to_move = broadcaster_clone.clone();
+                        let writer_stats_for_rtcp = writer_stats.clone();
+                        let writer_quality_for_rtcp = writer_quality.clone();
+                        let writer_bandwidth_for_rtcp = writer_bandwidth.clone();
+                        let nack_track = local_track.clone();
+                        let nack_buffer_for_rtcp = writer_nack_buffer.clone();
+                        let rtx_seq_for_rtcp = rtx_seq.clone();
+                        let writer_congestion_for_rtcp = writer_congestion.clone();
+                        let quality_event_tx = other_peer_event_tx.clone();
+                        let quality_target_user_id = source_user_id.clone();
+                        let quality_stream_id = track_stream_id_clone.clone();
+                        let quality_tracks = tracks_map.clone();
+                        let quality_room_id = room_id.clone();
+                        let quality_subscriber_id = other_peer_user_id.clone();
+                        let quality_track_id = track_id_clone.clone();
+                        let connector_for_rtcp = connector_for_subscriber.clone();
+                        let keyframe_source_user_id = source_user_id.clone();
+                        let keyframe_room_id = room_id.clone();
+                        let keyframe_stream_id = track_stream_id_clone.clone();
+                        let keyframe_track_kind = track_kind_clone.clone();
                         tokio::spawn(async move {
                             let mut rtcp_buf = vec![0u8; 1500];
+                            // Latest loss percentage from this subscriber's Receiver
+                            // Reports, folded into `TransportLayerCc` handling below
+                            // since TWCC feedback itself carries no loss field.
+                            let mut last_loss_pct: f32 = 0.0;
                             while let Ok((packets, _)) = sender_clone.read(&mut rtcp_buf).await {
                                 for packet in packets {
                                     if packet.as_any().is::<PictureLossIndication>() {
+                                        writer_stats_for_rtcp.record_pli();
                                         broadcaster_to_move.request_keyframe().await;
+                                        if let Some(connector) = &connector_for_rtcp {
+                                            connector.emit(
+                                                crate::connector::ConnectorEvent::new(
+                                                    crate::connector::ConnectorEventType::KeyframeRequested,
+                                                    keyframe_room_id.clone(),
+                                                    keyframe_source_user_id.clone(),
+                                                )
+                                                .with_track(
+                                                    keyframe_stream_id.clone(),
+                                                    keyframe_track_kind.clone(),
+                                                ),
+                                            );
+                                        }
+                                    } else if let Some(nack) =
+                                        packet.as_any().downcast_ref::<TransportLayerNack>()
+                                    {
+                                        writer_stats_for_rtcp.record_nack();
+                                        if !broadcaster_to_move.do_retransmission() {
+                                            // Retransmission is disabled for this track (typically
+                                            // audio, where a resend usually arrives too late for
+                                            // playout anyway); just record the loss.
+                                            continue;
+                                        }
+                                        for pair in &nack.nacks {
+                                            for seq in pair.packet_list() {
+                                                match nack_buffer_for_rtcp.get(seq).await {
+                                                    Some(packet) => {
+                                                        let resend = match rtx_payload_type {
+                                                            Some(rtx_pt) => {
+                                                                let rtx_sequence_number = rtx_seq_for_rtcp.fetch_add(
+                                                                    1,
+                                                                    std::sync::atomic::Ordering::Relaxed,
+                                                                );
+                                                                crate::broadcaster::wrap_as_rtx(
+                                                                    &packet,
+                                                                    rtx_ssrc,
+                                                                    rtx_pt,
+                                                                    rtx_sequence_number,
+                                                                )
+                                                            }
+                                                            None => packet,
+                                                        };
+                                                        let _ = nack_track.write_rtp(&resend).await;
+                                                    }
+                                                    None => {
+                                                        debug!(
+                                                            seq,
+                                                            "[SFU] NACK for packet no longer in resend buffer"
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    } else if let Some(rr) =
+                                        packet.as_any().downcast_ref::<ReceiverReport>()
+                                    {
+                                        for report in &rr.reports {
+                                            writer_stats_for_rtcp
+                                                .record_receiver_report(report.total_lost, report.jitter);
+
+                                            let loss_pct =
+                                                f32::from(report.fraction_lost) / 255.0 * 100.0;
+                                            last_loss_pct = loss_pct;
+                                            let rtt_ms = compute_rtt_ms(
+                                                report.last_sender_report,
+                                                report.delay,
+                                            )
+                                            .unwrap_or(0);
+                                            writer_stats_for_rtcp
+                                                .round_trip_time_ms
+                                                .store(rtt_ms, std::sync::atomic::Ordering::Relaxed);
+
+                                            if let Some(score) = writer_quality_for_rtcp
+                                                .observe(loss_pct, rtt_ms)
+                                                .await
+                                            {
+                                                quality_event_tx
+                                                    .emit(pb::sfu::sfu_event::Payload::ConnectionQuality(
+                                                        pb::sfu::ConnectionQualityEvent {
+                                                            target_user_id:
+                                                                quality_target_user_id.clone(),
+                                                            stream_id: quality_stream_id.clone(),
+                                                            score: score as u32,
+                                                        },
+                                                    ))
+                                                    .await;
+                                            }
+
+                                            // Loss-based bandwidth estimate (see
+                                            // `crate::bandwidth`): once sustained high loss
+                                            // or sustained low loss has held for the
+                                            // estimator's hysteresis window, step this
+                                            // subscriber to the next lower or next higher
+                                            // simulcast layer rather than leaving it stuck
+                                            // wherever it started. The delay-based
+                                            // `crate::congestion::CongestionController`
+                                            // (fed by TWCC feedback below) overrides a
+                                            // `Hold`/`Increase` with `Decrease` while it
+                                            // considers this leg over-using the path, since
+                                            // it reacts to queueing delay before loss ever
+                                            // shows up in the receiver reports.
+                                            let mut step =
+                                                writer_bandwidth_for_rtcp.observe(loss_pct).await;
+                                            if writer_congestion_for_rtcp.is_congested() {
+                                                step = Some(crate::bandwidth::LayerStep::Decrease);
+                                            }
+                                            if let Some(step) = step {
+                                                let candidates: Vec<(String, Arc<TrackBroadcaster>)> =
+                                                    quality_tracks
+                                                        .iter()
+                                                        .filter(|entry| {
+                                                            let (t_room, t_user, t_stream, t_track, _) =
+                                                                entry.key();
+                                                            t_room == &quality_room_id
+                                                                && t_user == &quality_target_user_id
+                                                                && t_stream == &quality_stream_id
+                                                                && t_track == &quality_track_id
+                                                        })
+                                                        .map(|entry| {
+                                                            (entry.key().4.clone(), entry.value().clone())
+                                                        })
+                                                        .collect();
+
+                                                let mut current_rid = None;
+                                                for (rid, broadcaster) in &candidates {
+                                                    if broadcaster.has_writer(&quality_subscriber_id).await
+                                                    {
+                                                        current_rid = Some(rid.clone());
+                                                        break;
+                                                    }
+                                                }
+
+                                                if let Some(current_rid) = current_rid {
+                                                    let new_rid = match step {
+                                                        crate::bandwidth::LayerStep::Decrease => {
+                                                            crate::simulcast::lower_rid(&current_rid)
+                                                        }
+                                                        crate::bandwidth::LayerStep::Increase => {
+                                                            crate::simulcast::higher_rid(&current_rid)
+                                                        }
+                                                        crate::bandwidth::LayerStep::Hold => None,
+                                                    };
+
+                                                    if let Some(new_rid) = new_rid {
+                                                        match crate::simulcast::switch_subscriber_layer(
+                                                            &candidates,
+                                                            &quality_subscriber_id,
+                                                            new_rid,
+                                                        )
+                                                        .await
+                                                        {
+                                                            Ok(()) => info!(
+                                                                subscriber = %quality_subscriber_id,
+                                                                from = %current_rid,
+                                                                to = %new_rid,
+                                                                ?step,
+                                                                "[SFU] Auto-switched subscriber simulcast layer"
+                                                            ),
+                                                            Err(reason) => debug!(
+                                                                subscriber = %quality_subscriber_id,
+                                                                %reason,
+                                                                "[SFU] Auto layer-switch attempt failed"
+                                                            ),
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    } else if let Some(tcc) =
+                                        packet.as_any().downcast_ref::<TransportLayerCc>()
+                                    {
+                                        writer_congestion_for_rtcp
+                                            .on_feedback(tcc, last_loss_pct)
+                                            .await;
                                     }
                                 }
                             }
                         });
 
-                        let params = rtp_sender.get_parameters().await;
-                        let ssrc = params.encodings.first().map(|e| e.ssrc).unwrap_or(0);
-                        let pt = if let Some(codec) = params.rtp_parameters.codecs.first() {
-                            codec.payload_type
-                        } else {
-                            // Fallback to incoming PT if we can't find a negotiated one (better than 0)
-                            let incoming_pt = track_for_pt.payload_type().try_into().unwrap_or(0);
-                            warn!(incoming_pt = %incoming_pt, "[SFU] Outgoing codecs empty, falling back to incoming PT");
-                            incoming_pt
-                        };
                         info!(outgoing_pt = %pt, ssrc = %ssrc, "[SFU] on_track forwarding: Resolved Outgoing PT");
-                        broadcaster_clone.add_writer(local_track, ssrc, pt).await;
+                        broadcaster_clone
+                            .add_writer(
+                                local_track,
+                                ssrc,
+                                pt,
+                                other_peer_user_id.clone(),
+                                writer_stats,
+                                writer_quality,
+                                other_peer_pc,
+                                rtp_sender,
+                                writer_nack_buffer,
+                                writer_congestion,
+                                twcc_extension_id,
+                            )
+                            .await;
 
                         // Delayed Keyframe Request - Burst Mode to ensure delivery after DTLS
                         broadcaster_clone.clone().schedule_pli_retry();
                         other_peer_track_mapping
                             .insert(track_stream_id_clone.clone(), source_user_id.clone());
+                        other_peer_subscribed_tracks
+                            .lock()
+                            .await
+                            .insert(subscriber_track_key);
 
-                        // Use unified renegotiation helper
-                        perform_renegotiation(
-                            other_peer_pc.clone(),
-                            other_peer_event_tx.clone(),
-                            other_peer_user_id.clone(),
-                            other_peer_signaling_lock.clone(),
-                            Some(pb::signaling::TrackAddedEvent {
+                        // Queue a renegotiation instead of performing it
+                        // inline, so concurrent track additions to the same
+                        // subscriber coalesce into a single offer (see
+                        // `crate::peer_manager::NegotiationOp`).
+                        let available_rids: Vec<String> = rids_tracks_map
+                            .iter()
+                            .filter(|entry| {
+                                let (t_room, t_user, t_stream, _t_track, t_rid) = entry.key();
+                                t_room == &rids_room_id
+                                    && t_user == &source_user_id
+                                    && t_stream == &track_stream_id_clone
+                                    && !t_rid.is_empty()
+                            })
+                            .map(|entry| entry.key().4.clone())
+                            .collect();
+
+                        let _ = other_peer_ops_tx.send(crate::peer_manager::NegotiationOp::Renegotiate {
+                            track_event: Some(pb::signaling::TrackAddedEvent {
                                 user_id: source_user_id,
                                 stream_id: track_stream_id_clone,
                                 track_kind: track_kind_clone,
+                                available_rids,
                             }),
-                        )
-                        .await;
+                        });
                     });
                 }
+                }
             }
 
             // 3. Start Forwarding Loop
             // Read from `track` (Remote), Write to `broadcaster` (Locals)
             let _media_ssrc = track.ssrc();
             let track_id_log = track.id().to_owned();
-            let mime_type = track.codec().capability.mime_type.to_lowercase();
+            let keyframe_detector =
+                crate::keyframe::detector_for_mime_type(&track.codec().capability.mime_type);
 
             tokio::spawn(async move {
                 let mut packet_count = 0;
                 info!(track = %track_id_log, "[SFU] Starting read_rtp loop");
                 loop {
                     match track.read_rtp().await {
-                        Ok((mut packet, _)) => {
+                        Ok((packet, _)) => {
                             packet_count += 1;
                             if packet_count == 1 {
                                 info!(track = %track_id_log, "[SFU] First packet received");
                             }
 
-                            // Keyframe Detection
-                            let is_keyframe = if packet.payload.len() > 0 {
-                                if mime_type.contains("vp8") {
-                                    // VP8: S-bit is 0 for start of partition? No, Key frame is bit 0 of first byte == 0
-                                    // (payload[0] & 0x01) == 0
-                                    (packet.payload[0] & 0x01) == 0
-                                } else if mime_type.contains("h264") {
-                                    let nal_type = packet.payload[0] & 0x1F;
-                                    if nal_type == 5 {
-                                        true // IDR
-                                    } else if nal_type == 28 && packet.payload.len() > 1 {
-                                        // FU-A
-                                        let s_bit = (packet.payload[1] & 0x80) != 0;
-                                        let inner_type = packet.payload[1] & 0x1F;
-                                        s_bit && inner_type == 5
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            } else {
-                                false
-                            };
+                            broadcaster
+                                .inbound_stats
+                                .record_packet(packet.header.sequence_number, packet.payload.len());
+                            broadcaster
+                                .inbound_stats
+                                .record_arrival(packet.header.timestamp)
+                                .await;
+
+                            let is_keyframe = keyframe_detector.is_keyframe(&packet.payload);
 
                             if is_keyframe {
                                 broadcaster.mark_keyframe_received();
@@ -210,8 +558,12 @@ pub fn attach_track_handler(
                                 );
                             }
 
-                            // Use optimized broadcast method
-                            broadcaster.broadcast(&mut packet).await;
+                            // Muted tracks are dropped here rather than torn
+                            // down, so unmuting doesn't need a fresh track or
+                            // renegotiation.
+                            if broadcaster.is_enabled() {
+                                broadcaster.broadcast(&packet);
+                            }
                         }
                         Err(e) => {
                             warn!(
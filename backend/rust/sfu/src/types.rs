@@ -1,16 +1,29 @@
 use crate::broadcaster::TrackBroadcaster;
-use crate::pb::sfu::SfuEvent;
+use crate::pb::sfu::{sfu_event::Payload, SfuEvent};
 use crate::peer_manager::Peer;
+use arc_swap::ArcSwapOption;
 use dashmap::DashMap;
-use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as SyncMutex};
+use tokio::sync::{mpsc, RwLock};
 use tonic::Status;
 
-/// (RoomID, UserID)
-pub type SessionKey = (String, String);
+/// (RoomID, UserID, SessionID)
+///
+/// The `SessionID` distinguishes a reconnect or a second concurrent tab for
+/// the same `(RoomID, UserID)` from whatever `Peer` is already registered,
+/// so it gets its own `PeerMap` entry instead of clobbering the live one's
+/// `event_tx`/`pc`. See `RoomState::sessions_by_user` for resolving a
+/// caller's session(s) when only `(RoomID, UserID)` is known.
+pub type SessionKey = (String, String, String);
 
-/// (RoomID, UserID, StreamID, TrackID)
-pub type TrackKey = (String, String, String, String);
+/// (RoomID, UserID, StreamID, TrackID, Rid)
+///
+/// `Rid` is the simulcast RID extension value (`""` for non-simulcast
+/// tracks), so each encoding a publisher sends for the same logical track
+/// gets its own [`crate::broadcaster::TrackBroadcaster`] entry.
+pub type TrackKey = (String, String, String, String, String);
 
 /// Channel to send events back to the signaling server (Go)
 pub type EventSender = mpsc::Sender<Result<SfuEvent, Status>>;
@@ -21,8 +34,151 @@ pub type PeerMap = Arc<DashMap<SessionKey, Peer>>;
 /// Thread-safe map of broadcasters
 pub type TrackMap = Arc<DashMap<TrackKey, Arc<TrackBroadcaster>>>;
 
-/// Wrapped event sender with mutex and option
-pub type SharedEventSender = Arc<Mutex<Option<EventSender>>>;
+/// Number of recently emitted events an [`EventStream`] retains so a
+/// `ListenEvents` stream that reconnects can resume via
+/// `ListenRequest::resume_from_sequence` instead of missing events that
+/// fired while it was down.
+const EVENT_BUFFER_CAPACITY: usize = 256;
+
+/// Per-session event channel: dispatches to the outbound [`EventSender`]
+/// without ever awaiting a lock. Every event a peer emits (track updates,
+/// renegotiation offers, stats, room updates...) goes through
+/// [`EventStream::emit`], which stamps it with a monotonic `sequence`,
+/// records it in a bounded replay buffer, and forwards it to the current
+/// sender, if any.
+///
+/// The sender itself lives in an [`ArcSwapOption`] so readers load it without
+/// awaiting anything; `perform_renegotiation`-style callers that used to take
+/// `event_tx.lock().await` twice per offer (once for the track event, once
+/// for the offer) now go through a handle shared via `&self` instead of
+/// `&mut self` behind a `tokio::sync::Mutex`. The buffer/sequence bookkeeping
+/// still needs a critical section, but it's a plain, never-held-across-an-
+/// await [`std::sync::Mutex`] — `attach` (see below) folds the replay
+/// snapshot and the new sender swap into that same section so a reconnecting
+/// stream can't observe an event twice or miss it.
+pub struct EventStream {
+    tx: ArcSwapOption<EventSender>,
+    next_sequence: AtomicU64,
+    buffer: SyncMutex<VecDeque<SfuEvent>>,
+}
+
+impl EventStream {
+    pub fn new() -> Self {
+        Self {
+            tx: ArcSwapOption::empty(),
+            next_sequence: AtomicU64::new(0),
+            buffer: SyncMutex::new(VecDeque::with_capacity(EVENT_BUFFER_CAPACITY)),
+        }
+    }
+
+    /// Attaches the sender for a (re)connected stream. The sequence counter
+    /// and replay buffer are untouched, so a resumed stream's
+    /// `resume_from_sequence` still lines up with events emitted while no
+    /// sender was attached.
+    pub fn set_sender(&self, tx: EventSender) {
+        self.tx.store(Some(Arc::new(tx)));
+    }
+
+    pub fn clear_sender(&self) {
+        self.tx.store(None);
+    }
+
+    pub fn has_sender(&self) -> bool {
+        self.tx.load().is_some()
+    }
+
+    /// Stamps `payload` with the next sequence number, records it in the
+    /// replay buffer, and forwards it to the live stream if one is attached.
+    /// Silently drops the event when nothing is listening, matching the
+    /// previous `Option<EventSender>` behavior.
+    pub async fn emit(&self, payload: Payload) {
+        let (event, tx) = {
+            let mut buffer = self.buffer.lock().unwrap();
+            let event = SfuEvent {
+                payload: Some(payload),
+                sequence: self.next_sequence.fetch_add(1, Ordering::SeqCst),
+            };
+            if buffer.len() >= EVENT_BUFFER_CAPACITY {
+                buffer.pop_front();
+            }
+            buffer.push_back(event.clone());
+            // Snapshotting the sender inside the same critical section as
+            // the buffer push is what keeps `attach` race-free: whichever of
+            // this push or a concurrent `attach` takes the lock first is the
+            // one the other observes, so an event is never both replayed to
+            // a newly attached stream *and* delivered to it live below.
+            (event, self.tx.load_full())
+        };
+        if let Some(tx) = tx {
+            let _ = tx.send(Ok(event)).await;
+        }
+    }
+
+    /// Atomically replays everything after `resume_from` (if given) and
+    /// attaches `tx` as the new live sender, so no event emitted concurrently
+    /// with reconnection is either missed or delivered twice: the same
+    /// buffer lock that guards `emit`'s snapshot guards this whole operation.
+    pub fn attach(&self, tx: EventSender, resume_from: Option<u64>) -> Result<Vec<SfuEvent>, ()> {
+        let mut buffer = self.buffer.lock().unwrap();
+        let replay = match resume_from {
+            Some(from) => {
+                if let Some(oldest) = buffer.front() {
+                    if from + 1 < oldest.sequence {
+                        return Err(());
+                    }
+                }
+                buffer.iter().filter(|e| e.sequence > from).cloned().collect()
+            }
+            None => Vec::new(),
+        };
+        self.tx.store(Some(Arc::new(tx)));
+        Ok(replay)
+    }
+}
+
+impl Default for EventStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared event stream, handed to the peer's holder and whichever task is
+/// currently driving its `ListenEvents`/`Signal` stream. `EventStream`
+/// manages its own synchronization internally (see its doc comment), so this
+/// is a plain `Arc` rather than a lock wrapper.
+pub type SharedEventSender = Arc<EventStream>;
 
 /// Shared list of writers for a broadcaster
 pub type SharedBroadcasterWriters = Arc<RwLock<Vec<crate::broadcaster::BroadcasterWriter>>>;
+
+/// A room's participant registry, keyed by `UserID`. Populated as peers join
+/// via `create_session`/WHIP/WHEP and trimmed as they leave, so `signal` and
+/// `delete_session` can fan out incremental `RoomUpdate` events instead of
+/// clients inferring presence from track churn.
+pub struct RoomState {
+    pub participants: DashMap<String, crate::pb::sfu::ParticipantInfo>,
+    /// `UserID -> SessionID`s currently registered in `PeerMap` for this
+    /// room, so a request that only carries `(RoomID, UserID)` (no explicit
+    /// session) can resolve which concrete session(s) to address, and so a
+    /// stale session can be torn down independently of a user's other live
+    /// sessions.
+    pub sessions_by_user: DashMap<String, Vec<String>>,
+}
+
+impl RoomState {
+    pub fn new() -> Self {
+        Self {
+            participants: DashMap::new(),
+            sessions_by_user: DashMap::new(),
+        }
+    }
+}
+
+impl Default for RoomState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thread-safe map of rooms, keyed by `RoomID`.
+pub type RoomMap = Arc<DashMap<String, RoomState>>;
@@ -0,0 +1,421 @@
+//! HTTP ingress/egress per the WHIP (WebRTC-HTTP Ingestion Protocol) and WHEP
+//! (WebRTC-HTTP Egress Protocol) drafts, mounted next to the tonic
+//! `SfuService` so browsers, OBS, and `ffmpeg` can join a room without
+//! speaking the bespoke `CreateSession`/`HandleSignal` protobuf dance.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{post, Router};
+use tracing::{error, info};
+use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+
+use crate::media_setup::{ClockSignalingMode, IceServerConfig, MediaSetup};
+use crate::pb::sfu::sfu_service_server::SfuService as _;
+use crate::pb::sfu::DeleteSessionRequest;
+use crate::peer_manager::Peer;
+use crate::sfu_service::MySfu;
+use crate::track_handler;
+
+const SDP_CONTENT_TYPE: &str = "application/sdp";
+const TRICKLE_ICE_CONTENT_TYPE: &str = "application/trickle-ice-sdpfrag";
+
+/// Builds the WHIP/WHEP HTTP router. Serve this on its own address alongside
+/// the tonic `Server` (they share `MySfu`, so publishes/subscribes made
+/// through either protocol see each other's tracks).
+pub fn router(sfu: Arc<MySfu>) -> Router {
+    Router::new()
+        .route(
+            "/whip/{room}/{user}",
+            post(whip_publish).delete(whip_delete).patch(whip_trickle),
+        )
+        .route(
+            "/whep/{room}/{user}",
+            post(whep_subscribe).delete(whep_delete).patch(whep_trickle),
+        )
+        .with_state(sfu)
+}
+
+async fn whip_publish(
+    State(sfu): State<Arc<MySfu>>,
+    Path((room_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let grant = match verify_bearer_token(&sfu, &headers, &room_id, &user_id) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    if let Some(resp) = reject_if_room_full(&sfu, &room_id, &user_id) {
+        return resp;
+    }
+
+    if let Some(resp) = require_sdp_content_type(&headers) {
+        return resp;
+    }
+
+    let offer_sdp = match String::from_utf8(body.to_vec()) {
+        Ok(s) => s,
+        Err(_) => return bad_request("offer body must be UTF-8 SDP"),
+    };
+
+    let (pc, peer_ready) = match create_negotiated_peer(&sfu, &offer_sdp, true).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let _ = peer_ready;
+
+    let session_id = crate::id_types::SessionId::generate().to_string();
+    let pc = Arc::new(pc);
+    let peer = Peer::new(
+        pc.clone(),
+        user_id.clone(),
+        room_id.clone(),
+        session_id.clone(),
+        grant,
+    );
+    peer.register_ice_candidate_handler();
+
+    // Same publish-side wiring as `CreateSession`: forward this user's
+    // tracks to every other peer already in the room.
+    track_handler::attach_track_handler(
+        &pc,
+        user_id.clone(),
+        room_id.clone(),
+        session_id.clone(),
+        sfu.peers.clone(),
+        sfu.tracks.clone(),
+        sfu.connector.clone(),
+    );
+
+    let answer_sdp = match finish_answer(&pc).await {
+        Ok(sdp) => sdp,
+        Err(resp) => return resp,
+    };
+
+    sfu.peers
+        .insert((room_id.clone(), user_id.clone(), session_id.clone()), peer);
+    crate::sfu_service::register_session(&sfu.rooms, &room_id, &user_id, &session_id);
+    crate::sfu_service::room_participant_joined(&sfu.rooms, &sfu.peers, &sfu.room_manager, &room_id, &user_id)
+        .await;
+
+    info!(room = %room_id, user = %user_id, session = %session_id, "[WHIP] Published session");
+    created_response(&format!("/whip/{}/{}", room_id, user_id), answer_sdp)
+}
+
+async fn whep_subscribe(
+    State(sfu): State<Arc<MySfu>>,
+    Path((room_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let grant = match verify_bearer_token(&sfu, &headers, &room_id, &user_id) {
+        Ok(g) => g,
+        Err(resp) => return resp,
+    };
+
+    if let Some(resp) = reject_if_room_full(&sfu, &room_id, &user_id) {
+        return resp;
+    }
+
+    if let Some(resp) = require_sdp_content_type(&headers) {
+        return resp;
+    }
+
+    let offer_sdp = match String::from_utf8(body.to_vec()) {
+        Ok(s) => s,
+        Err(_) => return bad_request("offer body must be UTF-8 SDP"),
+    };
+
+    // WHEP peers only receive media, so skip the recvonly transceiver setup
+    // `configure_media_engine` adds for publishers.
+    let (pc, _) = match create_negotiated_peer(&sfu, &offer_sdp, false).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+
+    let session_id = crate::id_types::SessionId::generate().to_string();
+    let pc = Arc::new(pc);
+    let peer = Peer::new(
+        pc.clone(),
+        user_id.clone(),
+        room_id.clone(),
+        session_id.clone(),
+        grant,
+    );
+    peer.register_ice_candidate_handler();
+
+    MediaSetup::subscribe_to_existing_tracks(&peer, &user_id, &room_id, &sfu.tracks).await;
+
+    let answer_sdp = match finish_answer(&pc).await {
+        Ok(sdp) => sdp,
+        Err(resp) => return resp,
+    };
+
+    sfu.peers
+        .insert((room_id.clone(), user_id.clone(), session_id.clone()), peer);
+    crate::sfu_service::register_session(&sfu.rooms, &room_id, &user_id, &session_id);
+    crate::sfu_service::room_participant_joined(&sfu.rooms, &sfu.peers, &sfu.room_manager, &room_id, &user_id)
+        .await;
+
+    info!(room = %room_id, user = %user_id, session = %session_id, "[WHEP] Subscribed session");
+    created_response(&format!("/whep/{}/{}", room_id, user_id), answer_sdp)
+}
+
+async fn whip_delete(
+    state: State<Arc<MySfu>>,
+    path: Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    delete_session(state, path, headers).await
+}
+
+async fn whep_delete(
+    state: State<Arc<MySfu>>,
+    path: Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    delete_session(state, path, headers).await
+}
+
+/// Tears down a WHIP/WHEP session by reusing `DeleteSession`'s cleanup path.
+async fn delete_session(
+    State(sfu): State<Arc<MySfu>>,
+    Path((room_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(resp) = verify_bearer_token(&sfu, &headers, &room_id, &user_id) {
+        return resp;
+    }
+
+    // The WHIP/WHEP URL scheme only carries `(room_id, user_id)`, so this
+    // tears down every session that user holds, same as before session ids
+    // existed.
+    match sfu
+        .delete_session(tonic::Request::new(DeleteSessionRequest {
+            room_id,
+            user_id,
+            session_id: String::new(),
+        }))
+        .await
+    {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(status) => (StatusCode::NOT_FOUND, status.message().to_string()).into_response(),
+    }
+}
+
+async fn whip_trickle(
+    state: State<Arc<MySfu>>,
+    path: Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    trickle_ice(state, path, headers, body).await
+}
+
+async fn whep_trickle(
+    state: State<Arc<MySfu>>,
+    path: Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    trickle_ice(state, path, headers, body).await
+}
+
+/// Applies a trickled ICE fragment. Only candidate lines are read (no
+/// ufrag/pwd handling), which covers the common case of a server that keeps
+/// one ICE generation per session.
+async fn trickle_ice(
+    State(sfu): State<Arc<MySfu>>,
+    Path((room_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Err(resp) = verify_bearer_token(&sfu, &headers, &room_id, &user_id) {
+        return resp;
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with(TRICKLE_ICE_CONTENT_TYPE) {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("expected Content-Type: {}", TRICKLE_ICE_CONTENT_TYPE),
+        )
+            .into_response();
+    }
+
+    // The URL route only carries `(room_id, user_id)`; resolve the user's
+    // most recently created session in the room, same as `GetSessionStats`.
+    let Some(session_key) = crate::sfu_service::resolve_session_key(&sfu.rooms, &room_id, &user_id, "")
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let peer = match sfu.peers.get(&session_key) {
+        Some(p) => p,
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    let fragment = String::from_utf8_lossy(&body);
+    for line in fragment.lines() {
+        let Some(candidate) = line.trim().strip_prefix("a=candidate:") else {
+            continue;
+        };
+        let init = RTCIceCandidateInit {
+            candidate: format!("candidate:{}", candidate),
+            ..Default::default()
+        };
+        if let Err(e) = peer.pc.add_ice_candidate(init).await {
+            error!(room = %room_id, user = %user_id, error = %e, "[WHIP/WHEP] Failed to add trickled ICE candidate");
+        }
+    }
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Extracts a bearer token from `Authorization: Bearer <token>` (empty
+/// string if absent, which `AuthConfig::verify` only accepts when no
+/// `SFU_JWT_SECRET` is configured) and verifies it for `room_id`/`user_id`.
+fn verify_bearer_token(
+    sfu: &MySfu,
+    headers: &HeaderMap,
+    room_id: &str,
+    user_id: &str,
+) -> Result<crate::auth::RoomGrant, Response> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    sfu.auth.verify(token, room_id, user_id).map_err(|e| {
+        (StatusCode::UNAUTHORIZED, e.to_string()).into_response()
+    })
+}
+
+/// Rejects a new (not already present) user once `room_id` is at
+/// `max_participants_per_room`, mirroring `MySfu::create_session`'s gRPC
+/// check so a room can't be filled past capacity through the WHIP/WHEP door.
+fn reject_if_room_full(sfu: &MySfu, room_id: &str, user_id: &str) -> Option<Response> {
+    let room_id_typed = crate::id_types::RoomId::from(room_id);
+    let user_id_typed = crate::id_types::UserId::from(user_id);
+    let existing_users = sfu.room_manager.get_users(&room_id_typed);
+    if existing_users.len() >= crate::config::max_participants_per_room()
+        && !existing_users.contains(&user_id_typed)
+    {
+        return Some(
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("room {} is full ({} participants)", room_id, existing_users.len()),
+            )
+                .into_response(),
+        );
+    }
+    None
+}
+
+/// Rejects a WHIP/WHEP POST whose `Content-Type` isn't `application/sdp`,
+/// mirroring `trickle_ice`'s check of `TRICKLE_ICE_CONTENT_TYPE` for the
+/// PATCH fragment route.
+fn require_sdp_content_type(headers: &HeaderMap) -> Option<Response> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with(SDP_CONTENT_TYPE) {
+        return Some(
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("expected Content-Type: {}", SDP_CONTENT_TYPE),
+            )
+                .into_response(),
+        );
+    }
+    None
+}
+
+/// Creates a peer connection, optionally adds recvonly transceivers for a
+/// publisher, and applies the client's SDP offer as the remote description.
+async fn create_negotiated_peer(
+    sfu: &MySfu,
+    offer_sdp: &str,
+    is_publisher: bool,
+) -> Result<(webrtc::peer_connection::RTCPeerConnection, ()), Response> {
+    let config = MediaSetup::get_rtc_config(&IceServerConfig::from_env());
+    let pc = sfu.api.new_peer_connection(config).await.map_err(|e| {
+        error!(error = %e, "[WHIP/WHEP] Failed to create peer connection");
+        internal_error("failed to create session")
+    })?;
+
+    if is_publisher {
+        MediaSetup::configure_media_engine(&pc).await.map_err(|e| {
+            error!(error = %e, "[WHIP/WHEP] Failed to configure media engine");
+            internal_error("failed to configure media")
+        })?;
+    }
+
+    let desc = RTCSessionDescription::offer(offer_sdp.to_string())
+        .map_err(|e| bad_request(&format!("invalid SDP offer: {}", e)))?;
+    pc.set_remote_description(desc)
+        .await
+        .map_err(|e| bad_request(&format!("failed to apply offer: {}", e)))?;
+
+    Ok((pc, ()))
+}
+
+/// Creates the local answer, waits for ICE gathering, and returns the final
+/// SDP (mirrors the wait used by `CreateSession`/`HandleSignal`).
+async fn finish_answer(
+    pc: &webrtc::peer_connection::RTCPeerConnection,
+) -> Result<String, Response> {
+    let answer = pc
+        .create_answer(None)
+        .await
+        .map_err(|e| internal_error(&format!("failed to create answer: {}", e)))?;
+
+    let mut gather_complete = pc.gathering_complete_promise().await;
+    pc.set_local_description(answer)
+        .await
+        .map_err(|e| internal_error(&format!("failed to set local description: {}", e)))?;
+
+    let _ = tokio::time::timeout(
+        tokio::time::Duration::from_millis(1500),
+        gather_complete.recv(),
+    )
+    .await;
+
+    let sdp = pc.local_description().await.unwrap_or_default().sdp;
+    let sdp = MediaSetup::fix_dtls_role(sdp);
+    Ok(MediaSetup::apply_clock_signaling(
+        sdp,
+        ClockSignalingMode::from_env(),
+    ))
+}
+
+fn created_response(location: &str, sdp: String) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(SDP_CONTENT_TYPE),
+    );
+    headers.insert(
+        header::LOCATION,
+        HeaderValue::from_str(location).unwrap_or_else(|_| HeaderValue::from_static("/")),
+    );
+    (StatusCode::CREATED, headers, sdp).into_response()
+}
+
+fn bad_request(msg: &str) -> Response {
+    (StatusCode::BAD_REQUEST, msg.to_string()).into_response()
+}
+
+fn internal_error(msg: &str) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, msg.to_string()).into_response()
+}